@@ -0,0 +1,1367 @@
+extern crate heck;
+extern crate proc_macro2;
+extern crate serde_json;
+
+use self::heck::{CamelCase, SnakeCase};
+use self::proc_macro2::{Ident, Span, TokenStream};
+use self::serde_json::{Map, Value};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use graph::DependencyGraph;
+use markup::{example, inline, text, Reference};
+use pretty::pretty;
+use schema::{is_builtin, member_type, names, Schema};
+use visitor::SchemaVisitor;
+use {Alternate, Command, Description, Enum, Event, QemuType, Struct, Union};
+
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in",
+    "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "static", "struct", "super", "trait", "true", "try", "type", "typeof",
+    "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+// Types the generated code refers to, which definitions must not shadow
+const RESERVED_TYPES: &[&str] = &[
+    "Box",
+    "Deserialize",
+    "DeserializeOwned",
+    "Empty",
+    "Option",
+    "QmpCommand",
+    "QmpEvent",
+    "Result",
+    "Self",
+    "Serialize",
+    "String",
+    "Timestamp",
+    "Vec",
+];
+
+// heck only splits words on '-', '_' and spaces
+fn words(name: &str) -> String {
+    name.replace(|c: char| !c.is_alphanumeric(), "-")
+}
+
+fn ident(name: &str) -> Ident {
+    Ident::new(name, Span::call_site())
+}
+
+/// The Rust name of a QAPI type, enum value or command: CamelCase, with a
+/// Qemu prefix where that would not make a usable type name
+pub fn type_name(name: &str) -> String {
+    let camel = if !name.contains(|c: char| c.is_lowercase()) {
+        // SHUTDOWN and other names in capitals
+        words(&name.to_lowercase()).to_camel_case()
+    } else if name.contains(|c: char| !c.is_alphanumeric())
+        || name.starts_with(|c: char| c.is_lowercase())
+    {
+        words(name).to_camel_case()
+    } else {
+        name.to_string()
+    };
+    if camel.is_empty()
+        || camel.starts_with(|c: char| c.is_ascii_digit())
+        || RESERVED_TYPES.contains(&camel.as_str())
+    {
+        format!("Qemu{}", camel)
+    } else {
+        camel
+    }
+}
+
+/// The Rust name of a member: snake_case, with a qemu_ prefix for reserved
+/// words
+pub fn field_name(name: &str) -> String {
+    let snake = words(name).to_snake_case();
+    if snake.is_empty()
+        || snake.starts_with(|c: char| c.is_ascii_digit())
+        || KEYWORDS.contains(&snake.as_str())
+    {
+        format!("qemu_{}", snake)
+    } else {
+        snake
+    }
+}
+
+// The wire name serde's rename_all = "kebab-case" gives a field
+fn kebab_field(field: &str) -> String {
+    field.replace('_', "-")
+}
+
+// The wire name serde's rename_all = "kebab-case" gives a variant
+fn kebab_variant(variant: &str) -> String {
+    let mut kebab = String::new();
+    for (i, c) in variant.chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            kebab.push('-');
+        }
+        kebab.extend(c.to_lowercase());
+    }
+    kebab
+}
+
+// An explicit rename for names the container's rename_all gets wrong
+fn rename(wire: &str, derived: &str) -> TokenStream {
+    if wire == derived {
+        TokenStream::new()
+    } else {
+        quote!(#[serde(rename = #wire)])
+    }
+}
+
+/// The Rust types the generator uses for QAPI types.  The default table
+/// covers the builtin types; named types get the type generated for their
+/// definition.  A named type given its own entry is not generated, so a
+/// definition can be replaced by a hand-written type.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypeMap {
+    types: BTreeMap<String, String>,
+}
+
+impl Default for TypeMap {
+    fn default() -> Self {
+        let mut types = BTreeMap::new();
+        for &(qapi, rust) in &[
+            ("str", "String"),
+            ("int", "i64"),
+            ("int8", "i8"),
+            ("int16", "i16"),
+            ("int32", "i32"),
+            ("int64", "i64"),
+            ("uint8", "u8"),
+            ("uint16", "u16"),
+            ("uint32", "u32"),
+            ("uint64", "u64"),
+            ("size", "u64"),
+            ("number", "f64"),
+            ("bool", "bool"),
+            ("null", "()"),
+            ("any", "serde_json::Value"),
+            ("QType", "String"),
+        ] {
+            types.insert(qapi.to_string(), rust.to_string());
+        }
+        TypeMap { types }
+    }
+}
+
+impl TypeMap {
+    /// Use the Rust type `rust` wherever the schema refers to `qapi`
+    pub fn insert(&mut self, qapi: &str, rust: &str) -> Result<(), String> {
+        rust.parse::<TokenStream>()
+            .map_err(|e| format!("{}: {} is not valid Rust: {:?}", qapi, rust, e))?;
+        self.types.insert(qapi.to_string(), rust.to_string());
+        Ok(())
+    }
+
+    pub fn get(&self, qapi: &str) -> Option<&str> {
+        self.types.get(qapi).map(|t| t.as_str())
+    }
+}
+
+// Doc attributes for lines of text, which the printer turns back into ///
+// comments
+fn doc_lines<'a, I: IntoIterator<Item = &'a String>>(lines: I) -> TokenStream {
+    let lines = lines.into_iter().map(|line| {
+        let text = if line.is_empty() {
+            String::new()
+        } else {
+            format!(" {}", line)
+        };
+        quote!(#[doc = #text])
+    });
+    quote!(#(#lines)*)
+}
+
+// Where @name references in the docs of a definition link to, by name
+type Links = BTreeMap<String, String>;
+
+// The description of a member or enum value, if the doc block has one
+fn member_doc(doc: &Description, name: &str) -> Option<String> {
+    doc.parameters
+        .as_ref()?
+        .iter()
+        .find(|&(member, text)| member == name && !text.is_empty())
+        .map(|(_, text)| text.clone())
+}
+
+// The kinds of JSON value an alternate branch takes, in the order an
+// untagged enum should try them.  Integers are numbers too and enum values
+// are strings too, so the narrower kind comes first.  A struct also
+// deserializes from an array, so objects come last.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+enum JsonType {
+    Null,
+    Boolean,
+    Integer,
+    Number,
+    Enum,
+    String,
+    Array,
+    Object,
+}
+
+// A member of a struct, command or event, with the '*' taken off its name
+struct Member {
+    name: String,
+    optional: bool,
+    value: Value,
+    doc: Option<String>,
+}
+
+// Links for the members of a generated struct
+fn field_links(rust_name: &str, members: &[Member]) -> Links {
+    members
+        .iter()
+        .map(|m| (m.name.clone(), format!("{}::{}", rust_name, field_name(&m.name))))
+        .collect()
+}
+
+struct Generator<'a> {
+    schema: &'a Schema,
+    types: &'a TypeMap,
+    graph: DependencyGraph,
+    items: Vec<TokenStream>,
+    events: Vec<TokenStream>,
+    errors: Vec<String>,
+    // Doc references that don't resolve, found while building the items
+    diagnostics: RefCell<Vec<String>>,
+}
+
+impl<'a> Generator<'a> {
+    fn named_type(&self, name: &str) -> Result<TokenStream, String> {
+        if let Some(rust) = self.types.get(name) {
+            return Ok(rust.parse().unwrap());
+        }
+        if is_builtin(name) || self.schema.get(name).is_none() {
+            return Err(format!("{} is not defined", name));
+        }
+        let rust = ident(&type_name(name));
+        Ok(quote!(#rust))
+    }
+
+    // A type the generator names after a definition, like the enum of a
+    // flat union's branches.  It must not take the name of a type
+    // generated for another definition, or of one the generated code uses.
+    fn derived_name(&self, name: String) -> Result<String, String> {
+        let clash = RESERVED_TYPES.contains(&name.as_str())
+            || self.schema.definitions().iter().any(|d| match d {
+                QemuType::Command(_) | QemuType::Event(_) => false,
+                _ => d
+                    .name()
+                    .is_some_and(|n| self.types.get(n).is_none() && type_name(n) == name),
+            });
+        if clash {
+            return Err(format!("{} clashes with another type", name));
+        }
+        Ok(name)
+    }
+
+    // The type of a member, branch or return value of the definition owner.
+    // A Vec already puts its elements on the heap, so only types embedded
+    // directly may need a Box.
+    fn rust_type(&self, owner: &str, value: &Value) -> Result<TokenStream, String> {
+        match member_type(value) {
+            Value::String(name) => {
+                let rust = self.named_type(name)?;
+                Ok(if self.graph.needs_box(owner, name) {
+                    quote!(Box<#rust>)
+                } else {
+                    rust
+                })
+            }
+            Value::Array(a) if a.len() == 1 && a[0].is_string() => {
+                let element = self.named_type(a[0].as_str().unwrap())?;
+                Ok(quote!(Vec<#element>))
+            }
+            v => Err(format!("{} is not a type", v)),
+        }
+    }
+
+    // The parsed doc block of a definition
+    fn doc(&self, name: &str) -> Description {
+        self.schema
+            .sections
+            .iter()
+            .find(|s| s.qemu_type.name() == Some(name))
+            .map(|s| s.doc())
+            .unwrap_or_else(|| Description::parse(&[]))
+    }
+
+    // Links for the members of the struct generated for a struct or union
+    fn type_links(&self, name: &str) -> Links {
+        let none = Description::parse(&[]);
+        let members = match self.schema.get(name) {
+            _ if self.types.get(name).is_some() => return Links::new(),
+            Some(QemuType::Struct(s)) => self.members(&s.base, &s.fields, &none),
+            Some(QemuType::Union(u)) => self.members(&u.base, &Value::Null, &none),
+            _ => return Links::new(),
+        };
+        field_links(&type_name(name), &members.unwrap_or_default())
+    }
+
+    // What @name in the docs of a definition refers to: one of the members
+    // in links, or else another definition.  A member of another type is
+    // written @Type.member.
+    fn reference(&self, links: &Links, name: &str) -> Reference {
+        if let Some(path) = links.get(name) {
+            return Reference::Path(path.clone());
+        }
+        if self.types.get(name).is_some() || is_builtin(name) {
+            return Reference::Code;
+        }
+        match self.schema.get(name) {
+            Some(&QemuType::Command(_)) => {
+                return Reference::Path(format!("{}Command", type_name(name)))
+            }
+            Some(&QemuType::Event(_)) => {
+                return Reference::Path(format!("QmpEvent::{}", type_name(name)))
+            }
+            Some(_) => return Reference::Path(type_name(name)),
+            None => {}
+        }
+        match name.find('.') {
+            Some(dot) => match self.type_links(&name[..dot]).remove(&name[dot + 1..]) {
+                Some(path) => Reference::Path(path),
+                None => Reference::Broken,
+            },
+            None => Reference::Broken,
+        }
+    }
+
+    // Convert doc markup in the docs of owner, noting the references that
+    // don't resolve
+    fn markup<T, F>(&self, owner: &str, links: &Links, convert: F) -> T
+    where
+        F: FnOnce(&dyn Fn(&str) -> Reference, &mut Vec<String>) -> T,
+    {
+        let mut broken = Vec::new();
+        let converted = convert(&|name| self.reference(links, name), &mut broken);
+        // Nothing is generated for definitions the type map overrides
+        if self.types.get(owner).is_some() {
+            return converted;
+        }
+        let mut diagnostics = self.diagnostics.borrow_mut();
+        for name in broken {
+            let diagnostic = format!("{}: @{} is not a member or a definition", owner, name);
+            if !diagnostics.contains(&diagnostic) {
+                diagnostics.push(diagnostic);
+            }
+        }
+        converted
+    }
+
+    // The documentation of a definition: its body, then sections for what
+    // the body doesn't say
+    fn docs(&self, owner: &str, doc: &Description, links: &Links) -> TokenStream {
+        let lines = self.markup(owner, links, |resolve, broken| {
+            let mut lines = text(&doc.body, resolve, broken);
+            let mut section = |title: &str, content: Vec<String>| {
+                if !lines.is_empty() {
+                    lines.push(String::new());
+                }
+                lines.push(format!("# {}", title));
+                lines.push(String::new());
+                lines.extend(content);
+            };
+            if let Some(ref returns) = doc.returns {
+                section("Returns", vec![inline(returns, resolve, broken)]);
+            }
+            if !doc.errors.is_empty() {
+                let cases = &doc.errors;
+                section(
+                    "Errors",
+                    match cases.len() {
+                        1 => vec![inline(&cases[0], resolve, broken)],
+                        _ => cases
+                            .iter()
+                            .map(|case| format!("- {}", inline(case, resolve, broken)))
+                            .collect(),
+                    },
+                );
+            }
+            if let Some(ref transcript) = doc.example {
+                section("Example", example(transcript, resolve, broken));
+            }
+            if let Some(ref since) = doc.version_since {
+                section("Since", vec![since.to_string()]);
+            }
+            lines
+        });
+        doc_lines(&lines)
+    }
+
+    // The doc comment of a field or variant
+    fn member_docs(&self, owner: &str, doc: &Option<String>, links: &Links) -> TokenStream {
+        let lines: Vec<String> = doc
+            .iter()
+            .map(|text| self.markup(owner, links, |resolve, broken| inline(text, resolve, broken)))
+            .collect();
+        doc_lines(&lines)
+    }
+
+    // Members of a struct, with those of its bases first.  Members defined
+    // inline are described by doc, those of named types by their own docs.
+    fn members(
+        &self,
+        base: &Value,
+        data: &Value,
+        doc: &Description,
+    ) -> Result<Vec<Member>, String> {
+        let mut members = Vec::new();
+        match base {
+            Value::String(name) => match self.schema.get(name) {
+                Some(QemuType::Struct(s)) => {
+                    members.extend(self.members(&s.base, &s.fields, &self.doc(name))?);
+                }
+                _ => return Err(format!("{} is not a struct", name)),
+            },
+            &Value::Object(_) => members.extend(self.members(&Value::Null, base, doc)?),
+            _ => {}
+        }
+        match data {
+            Value::String(name) => members.extend(
+                self.members(data, &Value::Null, doc)
+                    .map_err(|_| format!("{} is not a struct", name))?,
+            ),
+            Value::Object(o) => {
+                for (key, value) in o {
+                    let name = key.trim_start_matches('*');
+                    members.push(Member {
+                        name: name.to_string(),
+                        optional: key.starts_with('*'),
+                        value: value.clone(),
+                        doc: member_doc(doc, name),
+                    });
+                }
+            }
+            _ => {}
+        }
+        Ok(members)
+    }
+
+    fn fields(
+        &self,
+        owner: &str,
+        members: &[Member],
+        links: &Links,
+    ) -> Result<Vec<TokenStream>, String> {
+        members
+            .iter()
+            .map(|member| {
+                let field = field_name(&member.name);
+                let rename = rename(&member.name, &kebab_field(&field));
+                let field = ident(&field);
+                let field_type = self.rust_type(owner, &member.value)?;
+                let doc = self.member_docs(owner, &member.doc, links);
+                // Optional members may be left out of the JSON altogether
+                Ok(if member.optional {
+                    quote! {
+                        #doc
+                        #rename
+                        #[serde(default, skip_serializing_if = "Option::is_none")]
+                        pub #field: Option<#field_type>,
+                    }
+                } else {
+                    quote! {
+                        #doc
+                        #rename
+                        pub #field: #field_type,
+                    }
+                })
+            })
+            .collect()
+    }
+
+    fn structure(
+        &self,
+        name: &str,
+        rust_name: &str,
+        base: &Value,
+        data: &Value,
+        doc: &Description,
+    ) -> Result<TokenStream, String> {
+        let members = self.members(base, data, doc)?;
+        let links = field_links(rust_name, &members);
+        let fields = self.fields(name, &members, &links)?;
+        let docs = self.docs(name, doc, &links);
+        let name = ident(rust_name);
+        Ok(quote! {
+            #docs
+            #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+            #[serde(rename_all = "kebab-case")]
+            pub struct #name {
+                #(#fields)*
+            }
+        })
+    }
+
+    fn enumeration(&self, e: &Enum, doc: &Description) -> Result<TokenStream, String> {
+        let name = type_name(&e.name);
+        let values = names(&e.fields);
+        let links: Links = values
+            .iter()
+            .map(|&value| (value.to_string(), format!("{}::{}", name, type_name(value))))
+            .collect();
+        let variants = values.into_iter().map(|value| {
+            let variant = type_name(value);
+            let rename = rename(value, &kebab_variant(&variant));
+            let variant = ident(&variant);
+            let variant_doc = self.member_docs(&e.name, &member_doc(doc, value), &links);
+            quote! {
+                #variant_doc
+                #rename
+                #variant,
+            }
+        });
+        let docs = self.docs(&e.name, doc, &links);
+        let name = ident(&name);
+        Ok(quote! {
+            #docs
+            #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+            #[serde(rename_all = "kebab-case")]
+            pub enum #name {
+                #(#variants)*
+            }
+        })
+    }
+
+    // A flat union is a single JSON object holding the base members, the
+    // discriminator and the members of the branch the discriminator picks.
+    // The base goes in a struct and the rest in an internally tagged enum
+    // flattened into it.
+    fn flat_union(
+        &self,
+        u: &Union,
+        discriminator: &str,
+        doc: &Description,
+    ) -> Result<TokenStream, String> {
+        let mut members = self.members(&u.base, &Value::Null, doc)?;
+        let tag = match members.iter().position(|m| m.name == discriminator) {
+            Some(i) => members.remove(i),
+            None => return Err(format!("{} is not a member of the base", discriminator)),
+        };
+        let (values, values_doc) = match member_type(&tag.value) {
+            Value::String(t) => match self.schema.get(t) {
+                Some(QemuType::Enum(e)) => (names(&e.fields), self.doc(t)),
+                _ => return Err(format!("discriminator {} is not an enum", discriminator)),
+            },
+            _ => return Err(format!("discriminator {} is not an enum", discriminator)),
+        };
+        let empty = Map::new();
+        let branches = u.data.as_object().unwrap_or(&empty);
+        if let Some(case) = branches.keys().find(|case| !values.contains(&case.as_str())) {
+            return Err(format!("{} is not a value of the discriminator", case));
+        }
+
+        // Members of the base take precedence over values of the
+        // discriminator
+        let name = type_name(&u.name);
+        let branch = self.derived_name(format!("{}Branch", name))?;
+        let mut links: Links = values
+            .iter()
+            .map(|&value| (value.to_string(), format!("{}::{}", branch, type_name(value))))
+            .collect();
+        links.extend(field_links(&name, &members));
+        links.insert(tag.name.clone(), format!("{}::{}", name, field_name(&tag.name)));
+
+        let mut variants = Vec::new();
+        for value in values {
+            let variant = type_name(value);
+            let rename = rename(value, &kebab_variant(&variant));
+            let variant = ident(&variant);
+            let doc = self.member_docs(&u.name, &member_doc(&values_doc, value), &links);
+            // Values without a branch have no members besides the base
+            variants.push(match branches.get(value) {
+                Some(branch) => {
+                    let branch_type = self.rust_type(&u.name, branch)?;
+                    quote! {
+                        #doc
+                        #rename
+                        #variant(#branch_type),
+                    }
+                }
+                None => quote! {
+                    #doc
+                    #rename
+                    #variant,
+                },
+            });
+        }
+
+        let fields = self.fields(&u.name, &members, &links)?;
+        let name = ident(&name);
+        let branch = ident(&branch);
+        let tag_doc = self.member_docs(&u.name, &tag.doc, &links);
+        let tag = ident(&field_name(discriminator));
+        let docs = self.docs(&u.name, doc, &links);
+        Ok(quote! {
+            #docs
+            #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+            #[serde(rename_all = "kebab-case")]
+            pub struct #name {
+                #(#fields)*
+                #tag_doc
+                #[serde(flatten)]
+                pub #tag: #branch,
+            }
+
+            #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+            #[serde(tag = #discriminator, rename_all = "kebab-case")]
+            pub enum #branch {
+                #(#variants)*
+            }
+        })
+    }
+
+    fn union(&self, u: &Union, doc: &Description) -> Result<TokenStream, String> {
+        if let Value::String(ref discriminator) = u.discriminator {
+            return self.flat_union(u, discriminator, doc);
+        }
+        let name = type_name(&u.name);
+        let empty = Map::new();
+        let cases = u.data.as_object().unwrap_or(&empty);
+        let links: Links = cases
+            .keys()
+            .map(|case| (case.clone(), format!("{}::{}", name, type_name(case))))
+            .collect();
+        let mut variants = Vec::new();
+        for (case, value) in cases {
+            let variant = type_name(case);
+            let rename = rename(case, &kebab_variant(&variant));
+            let variant = ident(&variant);
+            let variant_type = self.rust_type(&u.name, value)?;
+            let variant_doc = self.member_docs(&u.name, &member_doc(doc, case), &links);
+            variants.push(quote! {
+                #variant_doc
+                #rename
+                #variant(#variant_type),
+            });
+        }
+        let docs = self.docs(&u.name, doc, &links);
+        let name = ident(&name);
+        // QEMU sends a simple union as {"type": case, "data": {...}}
+        Ok(quote! {
+            #docs
+            #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+            #[serde(tag = "type", content = "data", rename_all = "kebab-case")]
+            pub enum #name {
+                #(#variants)*
+            }
+        })
+    }
+
+    // The JSON type a branch of an alternate accepts, in the order serde
+    // should try them
+    fn json_type(&self, value: &Value) -> Result<JsonType, String> {
+        let name = match member_type(value) {
+            Value::String(name) => name.as_str(),
+            &Value::Array(_) => return Ok(JsonType::Array),
+            v => return Err(format!("{} is not a type", v)),
+        };
+        Ok(match name {
+            "null" => JsonType::Null,
+            "bool" => JsonType::Boolean,
+            "number" => JsonType::Number,
+            "str" | "QType" => JsonType::String,
+            "any" => return Err("any can't be a branch of an alternate".to_string()),
+            _ if is_builtin(name) => JsonType::Integer,
+            _ => match self.schema.get(name) {
+                Some(&QemuType::Enum(_)) => JsonType::Enum,
+                Some(&QemuType::Struct(_)) | Some(&QemuType::Union(_)) => JsonType::Object,
+                Some(_) => return Err(format!("{} can't be a branch of an alternate", name)),
+                None => return Err(format!("{} is not defined", name)),
+            },
+        })
+    }
+
+    // QEMU picks the branch of an alternate by the JSON type of the value.
+    // An untagged enum takes the first variant that deserializes, which
+    // gives the same result as long as no two branches take the same kind
+    // of value.
+    fn alternate(&self, a: &Alternate, doc: &Description) -> Result<TokenStream, String> {
+        let mut branches: Vec<(&str, JsonType, &Value)> = Vec::new();
+        if let Value::Object(ref o) = a.data {
+            for (case, value) in o {
+                let json_type = self.json_type(value)?;
+                if let Some(&(other, _, _)) = branches.iter().find(|b| b.1 == json_type) {
+                    return Err(format!("branches {} and {} can't be told apart", other, case));
+                }
+                branches.push((case.as_str(), json_type, value));
+            }
+        }
+        branches.sort_by_key(|b| b.1);
+
+        let name = type_name(&a.name);
+        let links: Links = branches
+            .iter()
+            .map(|b| (b.0.to_string(), format!("{}::{}", name, type_name(b.0))))
+            .collect();
+        let mut variants = Vec::new();
+        for (case, json_type, value) in branches {
+            let variant = ident(&type_name(case));
+            let variant_doc = self.member_docs(&a.name, &member_doc(doc, case), &links);
+            variants.push(if json_type == JsonType::Null {
+                quote!(#variant_doc #variant,)
+            } else {
+                let branch_type = self.rust_type(&a.name, value)?;
+                quote!(#variant_doc #variant(#branch_type),)
+            });
+        }
+        let docs = self.docs(&a.name, doc, &links);
+        let name = ident(&name);
+        Ok(quote! {
+            #docs
+            #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+            #[serde(untagged)]
+            pub enum #name {
+                #(#variants)*
+            }
+        })
+    }
+
+    // The arguments of a command, in a struct named after the command.
+    // The Command suffix keeps it apart from the struct many commands take
+    // their arguments from.
+    fn command(&self, c: &Command, doc: &Description) -> Result<TokenStream, String> {
+        let name = self.derived_name(format!("{}Command", type_name(&c.name)))?;
+        let (fields, links) = match c.fields {
+            // A union can only be the arguments as a whole, with 'boxed'
+            Value::String(ref t) if matches!(self.schema.get(t), Some(&QemuType::Union(_))) => {
+                let arguments = self.rust_type(&c.name, &c.fields)?;
+                let field = quote! {
+                    #[serde(flatten)]
+                    pub arguments: #arguments,
+                };
+                (vec![field], self.type_links(t))
+            }
+            _ => {
+                let members = self.members(&Value::Null, &c.fields, doc)?;
+                let links = field_links(&name, &members);
+                (self.fields(&c.name, &members, &links)?, links)
+            }
+        };
+        let success_response = c.success_response != Value::Bool(false);
+        let ok = if c.returns.is_null() || !success_response {
+            quote!(Empty)
+        } else {
+            self.rust_type(&c.name, &c.returns)?
+        };
+        let wire = &c.name;
+        let allow_oob = c.allow_oob == Value::Bool(true);
+        let docs = self.docs(&c.name, doc, &links);
+        let name = ident(&name);
+        Ok(quote! {
+            #docs
+            #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+            #[serde(rename_all = "kebab-case")]
+            pub struct #name {
+                #(#fields)*
+            }
+
+            impl QmpCommand for #name {
+                type Ok = #ok;
+                const NAME: &'static str = #wire;
+                const ALLOW_OOB: bool = #allow_oob;
+                const SUCCESS_RESPONSE: bool = #success_response;
+            }
+        })
+    }
+
+    // The variant of QmpEvent for an event, and the struct for its data
+    // unless that is a type of its own
+    fn event(&self, e: &Event, doc: &Description) -> Result<(TokenStream, TokenStream), String> {
+        let variant = ident(&type_name(&e.name));
+        let wire = &e.name;
+        let (data, item, links) = match e.data {
+            Value::Null => (quote!(Empty), TokenStream::new(), Links::new()),
+            Value::String(ref t) => {
+                let data = self.rust_type(&e.name, &e.data)?;
+                (data, TokenStream::new(), self.type_links(t))
+            }
+            _ => {
+                let name = self.derived_name(format!("{}Event", type_name(&e.name)))?;
+                let links = field_links(&name, &self.members(&Value::Null, &e.data, doc)?);
+                // The members are described in the event's doc block
+                let mut data_doc = Description::parse(&[]);
+                data_doc.body = vec![format!("The data of the {} event", wire)];
+                data_doc.parameters = doc.parameters.clone();
+                let item = self.structure(&e.name, &name, &Value::Null, &e.data, &data_doc)?;
+                let name = ident(&name);
+                (quote!(#name), item, links)
+            }
+        };
+        // Events without data may leave it out
+        let default = if e.data.is_null() {
+            quote!(#[serde(default)])
+        } else {
+            TokenStream::new()
+        };
+        let docs = self.docs(&e.name, doc, &links);
+        let variant = quote! {
+            #docs
+            #[serde(rename = #wire)]
+            #variant {
+                #default
+                data: #data,
+                timestamp: Timestamp,
+            },
+        };
+        Ok((variant, item))
+    }
+
+    // Definitions with an entry in the type map are left to the user
+    fn add(&mut self, name: &str, item: Result<TokenStream, String>) {
+        if self.types.get(name).is_some() {
+            return;
+        }
+        match item {
+            Ok(item) => self.items.push(item),
+            Err(e) => self.errors.push(format!("{}: {}", name, e)),
+        }
+    }
+}
+
+impl<'a> SchemaVisitor for Generator<'a> {
+    fn visit_enum(&mut self, e: &Enum, description: &[String]) {
+        let item = self.enumeration(e, &Description::parse(description));
+        self.add(&e.name, item);
+    }
+
+    fn visit_struct(&mut self, s: &Struct, description: &[String]) {
+        let doc = Description::parse(description);
+        let item = self.structure(&s.name, &type_name(&s.name), &s.base, &s.fields, &doc);
+        self.add(&s.name, item);
+    }
+
+    fn visit_union(&mut self, u: &Union, description: &[String]) {
+        let item = self.union(u, &Description::parse(description));
+        self.add(&u.name, item);
+    }
+
+    fn visit_event(&mut self, e: &Event, description: &[String]) {
+        match self.event(e, &Description::parse(description)) {
+            Ok((variant, item)) => {
+                self.events.push(variant);
+                self.items.push(item);
+            }
+            Err(err) => self.errors.push(format!("{}: {}", e.name, err)),
+        }
+    }
+
+    fn visit_alternate(&mut self, a: &Alternate, description: &[String]) {
+        let item = self.alternate(a, &Description::parse(description));
+        self.add(&a.name, item);
+    }
+
+    fn visit_command(&mut self, c: &Command, description: &[String]) {
+        let item = self.command(c, &Description::parse(description));
+        self.add(&c.name, item);
+    }
+}
+
+impl Schema {
+    fn generate<'a>(&'a self, types: &'a TypeMap) -> Generator<'a> {
+        let mut generator = Generator {
+            schema: self,
+            types,
+            graph: DependencyGraph::new(self),
+            items: Vec::new(),
+            events: Vec::new(),
+            errors: Vec::new(),
+            diagnostics: RefCell::new(Vec::new()),
+        };
+        self.visit(&mut generator);
+        generator
+    }
+
+    /// Rust definitions for the schema as a token stream, for use from
+    /// build scripts and procedural macros
+    pub fn to_tokens(&self, types: &TypeMap) -> Result<TokenStream, String> {
+        let generator = self.generate(types);
+        if !generator.errors.is_empty() {
+            return Err(generator.errors.join("\n"));
+        }
+        let items = generator.items;
+        let events = generator.events;
+        Ok(quote! {
+            use serde::de::DeserializeOwned;
+            use serde::{Deserialize, Serialize};
+
+            /// A QMP command.  The command serializes to the "arguments" of
+            /// an "execute" request, and the "return" of a successful reply
+            /// deserializes to `Ok`.
+            pub trait QmpCommand: Serialize {
+                type Ok: DeserializeOwned;
+                /// The name to "execute"
+                const NAME: &'static str;
+                /// Whether the command may be run out-of-band
+                const ALLOW_OOB: bool;
+                /// Whether QEMU replies to the command at all
+                const SUCCESS_RESPONSE: bool;
+            }
+
+            /// The "return" of commands that don't return anything
+            #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+            pub struct Empty {}
+
+            #(#items)*
+
+            /// When QEMU emitted an event
+            #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+            pub struct Timestamp {
+                pub seconds: i64,
+                pub microseconds: i64,
+            }
+
+            /// An event from QEMU, as sent on the wire.  Events this schema
+            /// doesn't know about deserialize to `Unknown`, without their data.
+            #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+            #[serde(tag = "event")]
+            pub enum QmpEvent {
+                #(#events)*
+                #[serde(other)]
+                Unknown,
+            }
+        })
+    }
+
+    /// Formatted Rust source for the schema.  The result is a standalone
+    /// module that needs serde with its derive feature, and serde_json for
+    /// the 'any' type.
+    pub fn to_rust(&self) -> Result<String, String> {
+        self.to_rust_with(&TypeMap::default())
+    }
+
+    /// Formatted Rust source, with the given Rust types for QAPI types
+    pub fn to_rust_with(&self, types: &TypeMap) -> Result<String, String> {
+        Ok(pretty(self.to_tokens(types)?))
+    }
+
+    /// The @name references in doc comments that don't name a member of
+    /// the definition or another definition, as "definition: message".
+    /// They are left as plain code in the generated docs.
+    pub fn doc_diagnostics(&self, types: &TypeMap) -> Vec<String> {
+        self.generate(types).diagnostics.into_inner()
+    }
+}
+
+#[test]
+fn test_to_rust() {
+    let schema = Schema::parse(
+        r#"
+{ 'enum': 'RunState', 'data': [ 'debug', 'inmigrate', 'x86_64' ] }
+{ 'struct': 'StatusInfo',
+  'data': { 'running': 'bool', 'singlestep': 'bool', 'status': 'RunState', '*type': 'str',
+            'ram': 'size', 'load': 'number', 'cpus': 'uint8', 'extra': 'any',
+            'history': ['RunState'], '*tags': ['str'] } }
+{ 'command': 'query-status', 'returns': 'StatusInfo' }
+"#,
+    ).unwrap();
+    let expected = r#"use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// A QMP command.  The command serializes to the "arguments" of
+/// an "execute" request, and the "return" of a successful reply
+/// deserializes to `Ok`.
+pub trait QmpCommand: Serialize {
+    type Ok: DeserializeOwned;
+    /// The name to "execute"
+    const NAME: &'static str;
+    /// Whether the command may be run out-of-band
+    const ALLOW_OOB: bool;
+    /// Whether QEMU replies to the command at all
+    const SUCCESS_RESPONSE: bool;
+}
+
+/// The "return" of commands that don't return anything
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Empty {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RunState {
+    Debug,
+    Inmigrate,
+    #[serde(rename = "x86_64")]
+    X8664,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct StatusInfo {
+    pub running: bool,
+    pub singlestep: bool,
+    pub status: RunState,
+    #[serde(rename = "type")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub qemu_type: Option<String>,
+    pub ram: u64,
+    pub load: f64,
+    pub cpus: u8,
+    pub extra: serde_json::Value,
+    pub history: Vec<RunState>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct QueryStatusCommand {}
+
+impl QmpCommand for QueryStatusCommand {
+    type Ok = StatusInfo;
+    const NAME: &'static str = "query-status";
+    const ALLOW_OOB: bool = false;
+    const SUCCESS_RESPONSE: bool = true;
+}
+
+/// When QEMU emitted an event
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Timestamp {
+    pub seconds: i64,
+    pub microseconds: i64,
+}
+
+/// An event from QEMU, as sent on the wire.  Events this schema
+/// doesn't know about deserialize to `Unknown`, without their data.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event")]
+pub enum QmpEvent {
+    #[serde(other)]
+    Unknown,
+}
+"#;
+    assert_eq!(schema.to_rust().unwrap(), expected);
+
+    let mut types = TypeMap::default();
+    types.insert("RunState", "vm::RunState").unwrap();
+    let rust = schema.to_rust_with(&types).unwrap();
+    assert!(!rust.contains("pub enum RunState"));
+    assert!(rust.contains("    pub status: vm::RunState,\n"));
+    assert!(types.insert("RunState", "vm::(").is_err());
+}
+
+#[test]
+fn test_flat_union() {
+    let schema = Schema::parse(
+        r#"
+{ 'enum': 'Driver', 'data': [ 'file', 'null', 'host_device' ] }
+{ 'struct': 'Base', 'data': { 'driver': 'Driver', '*node-name': 'str' } }
+{ 'struct': 'FileOptions', 'data': { 'filename': 'str' } }
+{ 'union': 'Options', 'base': 'Base', 'discriminator': 'driver',
+  'data': { 'file': 'FileOptions', 'host_device': 'FileOptions' } }
+"#,
+    ).unwrap();
+    let expected = r#"#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Options {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_name: Option<String>,
+    #[serde(flatten)]
+    pub driver: OptionsBranch,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "driver", rename_all = "kebab-case")]
+pub enum OptionsBranch {
+    File(FileOptions),
+    Null,
+    #[serde(rename = "host_device")]
+    HostDevice(FileOptions),
+}
+"#;
+    let rust = schema.to_rust().unwrap();
+    assert!(rust.contains(expected));
+
+    let schema = Schema::parse(
+        r#"
+{ 'enum': 'Driver', 'data': [ 'file' ] }
+{ 'struct': 'Base', 'data': { 'driver': 'Driver' } }
+{ 'union': 'Options', 'base': 'Base', 'discriminator': 'driver',
+  'data': { 'nbd': 'Base' } }
+"#,
+    ).unwrap();
+    assert_eq!(
+        schema.to_rust().unwrap_err(),
+        "Options: nbd is not a value of the discriminator"
+    );
+
+    let schema = Schema::parse(
+        r#"
+{ 'struct': 'Qcow2Info', 'data': { 'compat': 'str' } }
+{ 'union': 'ImageInfoSpecific', 'data': { 'qcow2': 'Qcow2Info', 'raw_file': 'Qcow2Info' } }
+"#,
+    ).unwrap();
+    let expected = r#"#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "kebab-case")]
+pub enum ImageInfoSpecific {
+    Qcow2(Qcow2Info),
+    #[serde(rename = "raw_file")]
+    RawFile(Qcow2Info),
+}
+"#;
+    assert!(schema.to_rust().unwrap().contains(expected));
+
+    let schema = Schema::parse(
+        r#"
+{ 'enum': 'Driver', 'data': [ 'file' ] }
+{ 'struct': 'Base', 'data': { 'driver': 'Driver' } }
+{ 'struct': 'OptionsBranch', 'data': { 'filename': 'str' } }
+{ 'union': 'Options', 'base': 'Base', 'discriminator': 'driver',
+  'data': { 'file': 'OptionsBranch' } }
+"#,
+    ).unwrap();
+    assert_eq!(
+        schema.to_rust().unwrap_err(),
+        "Options: OptionsBranch clashes with another type"
+    );
+}
+
+#[test]
+fn test_alternate() {
+    let schema = Schema::parse(
+        r#"
+{ 'struct': 'Options', 'data': { 'driver': 'str' } }
+{ 'alternate': 'Ref', 'data': { 'definition': 'Options', 'reference': 'str' } }
+{ 'alternate': 'StrOrNull', 'data': { 's': 'str', 'n': 'null' } }
+"#,
+    ).unwrap();
+    let expected = r#"#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Ref {
+    Reference(String),
+    Definition(Options),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum StrOrNull {
+    N,
+    S(String),
+}
+"#;
+    assert!(schema.to_rust().unwrap().contains(expected));
+
+    let schema = Schema::parse(
+        r#"
+{ 'enum': 'Mode', 'data': [ 'auto' ] }
+{ 'alternate': 'Size', 'data': { 'fraction': 'number', 'bytes': 'int' } }
+{ 'alternate': 'Name', 'data': { 'name': 'str', 'mode': 'Mode' } }
+"#,
+    ).unwrap();
+    let rust = schema.to_rust().unwrap();
+    assert!(rust.contains("pub enum Size {\n    Bytes(i64),\n    Fraction(f64),\n}\n"));
+    assert!(rust.contains("pub enum Name {\n    Mode(Mode),\n    Name(String),\n}\n"));
+
+    let schema = Schema::parse(
+        r#"
+{ 'struct': 'Options', 'data': { 'driver': 'str' } }
+{ 'alternate': 'Twice', 'data': { 'a': 'Options', 'b': 'Options' } }
+{ 'alternate': 'Anything', 'data': { 'a': 'any' } }
+"#,
+    ).unwrap();
+    let error = schema.to_rust().unwrap_err();
+    assert!(error.contains("Twice: branches a and b can't be told apart"));
+    assert!(error.contains("Anything: any can't be a branch of an alternate"));
+}
+
+#[test]
+fn test_command() {
+    let schema = Schema::parse(
+        r#"
+{ 'struct': 'JobInfo', 'data': { 'id': 'str' } }
+{ 'command': 'query-jobs', 'returns': ['JobInfo'], 'allow-oob': true }
+{ 'command': 'job-cancel', 'data': { 'id': 'str', '*force': 'bool' },
+  'success-response': false }
+"#,
+    ).unwrap();
+    let expected = r#"pub struct QueryJobsCommand {}
+
+impl QmpCommand for QueryJobsCommand {
+    type Ok = Vec<JobInfo>;
+    const NAME: &'static str = "query-jobs";
+    const ALLOW_OOB: bool = true;
+    const SUCCESS_RESPONSE: bool = true;
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct JobCancelCommand {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub force: Option<bool>,
+}
+
+impl QmpCommand for JobCancelCommand {
+    type Ok = Empty;
+    const NAME: &'static str = "job-cancel";
+    const ALLOW_OOB: bool = false;
+    const SUCCESS_RESPONSE: bool = false;
+}
+"#;
+    assert!(schema.to_rust().unwrap().contains(expected));
+
+    let schema = Schema::parse(
+        r#"
+{ 'struct': 'StopCommand', 'data': { 'force': 'bool' } }
+{ 'command': 'stop', 'data': 'StopCommand' }
+{ 'command': 'qmp' }
+"#,
+    ).unwrap();
+    assert_eq!(
+        schema.to_rust().unwrap_err(),
+        "stop: StopCommand clashes with another type\n\
+         qmp: QmpCommand clashes with another type"
+    );
+}
+
+#[test]
+fn test_event() {
+    let schema = Schema::parse(
+        r#"
+{ 'struct': 'JobInfo', 'data': { 'id': 'str' } }
+{ 'event': 'SHUTDOWN', 'data': { 'guest': 'bool' } }
+{ 'event': 'STOP' }
+{ 'event': 'JOB_READY', 'data': 'JobInfo' }
+"#,
+    ).unwrap();
+    let expected = r#"#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ShutdownEvent {
+    pub guest: bool,
+}
+"#;
+    let rust = schema.to_rust().unwrap();
+    assert!(rust.contains(expected));
+    let expected = r#"#[serde(tag = "event")]
+pub enum QmpEvent {
+    #[serde(rename = "SHUTDOWN")]
+    Shutdown {
+        data: ShutdownEvent,
+        timestamp: Timestamp,
+    },
+    #[serde(rename = "STOP")]
+    Stop {
+        #[serde(default)]
+        data: Empty,
+        timestamp: Timestamp,
+    },
+    #[serde(rename = "JOB_READY")]
+    JobReady {
+        data: JobInfo,
+        timestamp: Timestamp,
+    },
+    #[serde(other)]
+    Unknown,
+}
+"#;
+    assert!(rust.ends_with(expected));
+
+    let schema = Schema::parse(
+        r#"
+{ 'enum': 'ShutdownEvent', 'data': [ 'host', 'guest' ] }
+{ 'event': 'SHUTDOWN', 'data': { 'cause': 'ShutdownEvent' } }
+{ 'event': 'QMP', 'data': { 'id': 'str' } }
+"#,
+    ).unwrap();
+    assert_eq!(
+        schema.to_rust().unwrap_err(),
+        "SHUTDOWN: ShutdownEvent clashes with another type\n\
+         QMP: QmpEvent clashes with another type"
+    );
+}
+
+#[test]
+fn test_docs() {
+    let schema = Schema::parse(
+        r#"
+##
+# @BlockdevBase:
+#
+# @device: the device name
+##
+{ 'struct': 'BlockdevBase', 'data': { 'device': 'str' } }
+
+##
+# @block_passwd:
+#
+# This command sets the password of a block device that has not been open
+# with a password and requires one, see @query-block.
+#
+# Returns: nothing on success
+#          If @device is not a valid block device, DeviceNotFound
+#
+# Example:
+#
+# -> { "execute": "block_passwd", "arguments": { "device": "ide0-hd0",
+#                                                "password": "12345" } }
+# <- { "return": {} }
+#
+# Since: 0.14.0
+##
+{ 'command': 'block_passwd', 'data': 'BlockPasswd' }
+
+##
+# @BlockPasswd:
+#
+# @password: the password to use for the device
+##
+{ 'struct': 'BlockPasswd', 'base': 'BlockdevBase', 'data': { 'password': 'str' } }
+
+##
+# @RunState:
+#
+# @running: the VM is running
+##
+{ 'enum': 'RunState', 'data': [ 'running', 'paused' ] }
+"#,
+    ).unwrap();
+    let rust = schema.to_rust().unwrap();
+    let expected = r#"/// This command sets the password of a block device that has not been open
+/// with a password and requires one, see `query-block`.
+///
+/// # Returns
+///
+/// nothing on success
+///
+/// # Errors
+///
+/// If [`device`](BlockPasswdCommand::device) is not a valid block device, DeviceNotFound
+///
+/// # Example
+///
+/// ```json
+/// -> { "execute": "block_passwd", "arguments": { "device": "ide0-hd0",
+///                                                "password": "12345" } }
+/// <- { "return": {} }
+/// ```
+///
+/// # Since
+///
+/// 0.14.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockPasswdCommand {
+    /// the device name
+    pub device: String,
+    /// the password to use for the device
+    pub password: String,
+}
+"#;
+    assert!(rust.contains(expected));
+    let expected = r#"pub enum RunState {
+    /// the VM is running
+    Running,
+    Paused,
+}
+"#;
+    assert!(rust.contains(expected));
+    assert_eq!(
+        schema.doc_diagnostics(&TypeMap::default()),
+        vec!["block_passwd: @query-block is not a member or a definition"]
+    );
+}
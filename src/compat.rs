@@ -0,0 +1,396 @@
+use std::collections::BTreeSet;
+
+use diff::{type_string, Change};
+use graph::DependencyGraph;
+use schema::{member_type, Schema};
+use QemuType;
+
+/// How a schema change affects existing QMP clients
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Compatibility {
+    Compatible,
+    /// Well behaved clients keep working, but might notice.  Output QEMU
+    /// can no longer send disappears from introspection, and new output
+    /// values have to be tolerated.
+    Risky,
+    Breaking,
+}
+
+/// A change along with its compatibility and the rule that decided it
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Classification {
+    pub change: Change,
+    pub compatibility: Compatibility,
+    pub reason: &'static str,
+}
+
+// Where a definition's members travel on the wire, looking at both versions
+// of the schema
+struct Usage<'a> {
+    old: &'a Schema,
+    new: &'a Schema,
+    input: BTreeSet<String>,
+    output: BTreeSet<String>,
+}
+
+impl<'a> Usage<'a> {
+    fn new(old: &'a Schema, new: &'a Schema) -> Usage<'a> {
+        let old_graph = DependencyGraph::new(old);
+        let new_graph = DependencyGraph::new(new);
+        let mut input = old_graph.input_types();
+        input.extend(new_graph.input_types());
+        let mut output = old_graph.output_types();
+        output.extend(new_graph.output_types());
+        Usage {
+            old,
+            new,
+            input,
+            output,
+        }
+    }
+
+    fn kind(&self, definition: &str) -> &'static str {
+        self.new
+            .get(definition)
+            .or_else(|| self.old.get(definition))
+            .map(|t| t.kind())
+            .unwrap_or("unknown")
+    }
+
+    fn is_input(&self, definition: &str) -> bool {
+        match self.kind(definition) {
+            "command" => true,
+            "event" => false,
+            _ => self.input.contains(definition),
+        }
+    }
+
+    fn is_output(&self, definition: &str) -> bool {
+        match self.kind(definition) {
+            "command" => false,
+            "event" => true,
+            _ => self.output.contains(definition),
+        }
+    }
+
+    fn is_branch(&self, definition: &str) -> bool {
+        matches!(self.kind(definition), "union" | "alternate")
+    }
+}
+
+// Whether two definitions look the same on the wire, whatever their names
+fn same_shape(old: &QemuType, new: &QemuType) -> bool {
+    match (old, new) {
+        (QemuType::Struct(o), QemuType::Struct(n)) => o.base == n.base && o.fields == n.fields,
+        (QemuType::Enum(o), QemuType::Enum(n)) => o.fields == n.fields,
+        (QemuType::Union(o), QemuType::Union(n)) => {
+            o.base == n.base && o.discriminator == n.discriminator && o.data == n.data
+        }
+        (QemuType::Alternate(o), QemuType::Alternate(n)) => o.data == n.data,
+        _ => false,
+    }
+}
+
+// The types of the branches of an alternate in the new schema
+fn branch_types(schema: &Schema, alternate: &str) -> Vec<String> {
+    match schema.get(alternate) {
+        Some(QemuType::Alternate(a)) => a
+            .data
+            .as_object()
+            .map(|o| o.values().map(|b| type_string(member_type(b))).collect())
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+// QEMU accepts everything it used to when a type becomes an alternate with
+// the old type as one of its branches.  The old type either keeps its name
+// or moves to a new name and stays the same.
+fn alternate_includes(usage: &Usage, alternate: &str, old: &str) -> bool {
+    branch_types(usage.new, alternate).iter().any(|branch| {
+        branch == old
+            || match (usage.old.get(old), usage.new.get(branch)) {
+                (Some(o), Some(n)) => same_shape(o, n),
+                _ => false,
+            }
+    })
+}
+
+type Rule = (Compatibility, &'static str);
+
+// Judge a change to a definition by how it affects clients sending it and
+// clients receiving it, and keep the worse of the two
+fn by_direction(usage: &Usage, definition: &str, input: Rule, output: Rule) -> Rule {
+    let mut rules: Vec<Rule> = Vec::new();
+    if usage.is_input(definition) {
+        rules.push(input);
+    }
+    if usage.is_output(definition) {
+        rules.push(output);
+    }
+    rules.into_iter().max_by_key(|r| r.0).unwrap_or((
+        Compatibility::Compatible,
+        "not used by any command or event",
+    ))
+}
+
+fn classify(usage: &Usage, change: &Change) -> Rule {
+    use self::Compatibility::*;
+
+    match change {
+        Change::Added { kind, .. } if kind == "event" => {
+            (Risky, "strict clients reject events they don't know")
+        }
+        &Change::Added { .. } => (Compatible, "adding definitions is compatible"),
+        Change::Removed { kind, .. } => match kind.as_str() {
+            "command" => (Breaking, "removing commands is incompatible"),
+            "event" => (Risky, "removing events only shows in introspection"),
+            _ => (Compatible, "types are not visible on the wire"),
+        },
+        Change::KindChanged { name, new, .. }
+            if new == "alternate" && alternate_includes(usage, name, name) =>
+        {
+            by_direction(
+                usage,
+                name,
+                (Compatible, "an alternate of the old type accepts what it did"),
+                (Breaking, "clients don't expect the other branches"),
+            )
+        }
+        &Change::KindChanged { .. } => (Breaking, "changing a definition's type is incompatible"),
+        &Change::DiscriminatorChanged { .. } => (
+            Breaking,
+            "changing the discriminator changes the wire format",
+        ),
+        Change::BaseChanged { definition, .. } => by_direction(
+            usage,
+            definition,
+            (Breaking, "changing the base changes the members"),
+            (Breaking, "changing the base changes the members"),
+        ),
+        &Change::MemberAdded {
+            ref definition,
+            optional,
+            ..
+        } => {
+            if usage.is_branch(definition) {
+                return (
+                    Compatible,
+                    "adding union and alternate branches is compatible",
+                );
+            }
+            let input = if optional {
+                (Compatible, "adding optional arguments is compatible")
+            } else {
+                (Breaking, "adding mandatory arguments is incompatible")
+            };
+            by_direction(
+                usage,
+                definition,
+                input,
+                (Risky, "strict clients reject return and event data they don't know"),
+            )
+        }
+        Change::MemberRemoved {
+            definition,
+            member,
+        } => {
+            if usage.is_branch(definition) {
+                return by_direction(
+                    usage,
+                    definition,
+                    (
+                        Breaking,
+                        "removing union and alternate branches is incompatible",
+                    ),
+                    (Risky, "removed branches only show in introspection"),
+                );
+            }
+            let was_optional = match usage.old.get(definition) {
+                Some(QemuType::Struct(s)) => s.fields.get(format!("*{}", member)).is_some(),
+                Some(QemuType::Event(e)) => e.data.get(format!("*{}", member)).is_some(),
+                _ => false,
+            };
+            let output = if was_optional {
+                (Risky, "removed optional data only shows in introspection")
+            } else {
+                (Breaking, "removing return and event data is incompatible")
+            };
+            by_direction(
+                usage,
+                definition,
+                (Breaking, "removing arguments is incompatible"),
+                output,
+            )
+        }
+        Change::MadeMandatory { definition, .. } => by_direction(
+            usage,
+            definition,
+            (
+                Breaking,
+                "making optional arguments mandatory is incompatible",
+            ),
+            (
+                Compatible,
+                "data that is always sent was allowed to be sent",
+            ),
+        ),
+        Change::MadeOptional { definition, .. } => by_direction(
+            usage,
+            definition,
+            (
+                Compatible,
+                "making mandatory arguments optional is compatible",
+            ),
+            (Breaking, "clients rely on mandatory data being sent"),
+        ),
+        Change::TypeChanged {
+            definition,
+            member: Some(_),
+            old,
+            new,
+        } if alternate_includes(usage, new, old) => by_direction(
+            usage,
+            definition,
+            (Compatible, "an alternate of the old type accepts what it did"),
+            (Breaking, "clients don't expect the other branches"),
+        ),
+        &Change::TypeChanged { .. } => (Breaking, "changing a member's type is incompatible"),
+        &Change::ReturnsChanged { .. } => (Breaking, "changing a return type is incompatible"),
+        Change::EnumValueAdded { definition, .. } => by_direction(
+            usage,
+            definition,
+            (Compatible, "adding enumeration values is compatible"),
+            (Risky, "clients have to cope with values they don't know"),
+        ),
+        Change::EnumValueRemoved { definition, .. } => by_direction(
+            usage,
+            definition,
+            (Breaking, "removing enumeration values is incompatible"),
+            (Risky, "removed values only show in introspection"),
+        ),
+        &Change::FeatureAdded { .. } | &Change::FeatureRemoved { .. } => {
+            (Compatible, "features don't change the wire format")
+        }
+    }
+}
+
+impl Schema {
+    /// The changes from this schema to `new`, each judged by QEMU's
+    /// compatibility rules for clients
+    pub fn check_compatibility(&self, new: &Schema) -> Vec<Classification> {
+        let usage = Usage::new(self, new);
+        self.diff(new)
+            .into_iter()
+            .map(|change| {
+                let (compatibility, reason) = classify(&usage, &change);
+                Classification {
+                    change,
+                    compatibility,
+                    reason,
+                }
+            })
+            .collect()
+    }
+
+    /// Whether a client written against this schema keeps working with `new`
+    pub fn is_compatible_with(&self, new: &Schema) -> bool {
+        self.check_compatibility(new)
+            .iter()
+            .all(|c| c.compatibility != Compatibility::Breaking)
+    }
+}
+
+#[test]
+fn test_check_compatibility() {
+    let old = Schema::parse(
+        r#"
+{ 'enum': 'RunState', 'data': [ 'running', 'paused', 'debug' ] }
+{ 'enum': 'Mode', 'data': [ 'fast', 'slow' ] }
+{ 'struct': 'StatusInfo', 'data': { 'status': 'RunState', '*reason': 'str' } }
+{ 'command': 'query-status', 'returns': 'StatusInfo' }
+{ 'command': 'set-mode', 'data': { 'mode': 'Mode', '*force': 'bool' } }
+"#,
+    )
+    .unwrap();
+    let new = Schema::parse(
+        r#"
+{ 'enum': 'RunState', 'data': [ 'running', 'paused' ] }
+{ 'enum': 'Mode', 'data': [ 'fast' ] }
+{ 'struct': 'StatusInfo', 'data': { 'status': 'RunState', '*extra': 'str' } }
+{ 'command': 'query-status', 'returns': 'StatusInfo' }
+{ 'command': 'set-mode', 'data': { 'mode': 'Mode', 'force': 'bool', '*x': 'int' } }
+"#,
+    )
+    .unwrap();
+
+    let result: Vec<(String, Compatibility)> = old
+        .check_compatibility(&new)
+        .into_iter()
+        .map(|c| (c.change.to_string(), c.compatibility))
+        .collect();
+    assert_eq!(
+        result,
+        vec![
+            ("- RunState value debug".to_string(), Compatibility::Risky),
+            ("- Mode value slow".to_string(), Compatibility::Breaking),
+            ("- StatusInfo.reason".to_string(), Compatibility::Risky),
+            ("+ StatusInfo.*extra".to_string(), Compatibility::Risky),
+            (
+                "~ set-mode.force: optional -> mandatory".to_string(),
+                Compatibility::Breaking,
+            ),
+            ("+ set-mode.*x".to_string(), Compatibility::Compatible),
+        ]
+    );
+    assert!(!old.is_compatible_with(&new));
+    assert!(old.is_compatible_with(&old));
+}
+
+#[test]
+fn test_additions_and_alternates() {
+    let old = Schema::parse(
+        r#"
+{ 'struct': 'Options', 'data': { 'driver': 'str' } }
+{ 'struct': 'JobInfo', 'data': { 'id': 'str' } }
+{ 'command': 'query-jobs', 'returns': [ 'JobInfo' ] }
+{ 'command': 'job-set', 'data': { 'id': 'str', 'speed': 'int' } }
+{ 'command': 'add', 'data': { 'options': 'Options' } }
+{ 'event': 'JOB_STATUS', 'data': { 'id': 'str' } }
+"#,
+    )
+    .unwrap();
+    let new = Schema::parse(
+        r#"
+{ 'struct': 'InlineOptions', 'data': { 'driver': 'str' } }
+{ 'alternate': 'Options', 'data': { 'definition': 'InlineOptions', 'reference': 'str' } }
+{ 'alternate': 'Speed', 'data': { 'bytes': 'int', 'mode': 'str' } }
+{ 'struct': 'JobInfo', 'data': { 'id': 'str', 'status': 'str' } }
+{ 'command': 'query-jobs', 'returns': [ 'JobInfo' ] }
+{ 'command': 'job-set', 'data': { 'id': 'str', 'speed': 'Speed' } }
+{ 'command': 'add', 'data': { 'options': 'Options' } }
+{ 'event': 'JOB_STATUS', 'data': { 'id': 'str', 'status': 'str' } }
+{ 'event': 'JOB_DONE', 'data': { 'id': 'str' } }
+"#,
+    )
+    .unwrap();
+
+    let result: Vec<(String, Compatibility)> = old
+        .check_compatibility(&new)
+        .into_iter()
+        .map(|c| (c.change.to_string(), c.compatibility))
+        .collect();
+    assert_eq!(
+        result,
+        vec![
+            ("~ Options: struct -> alternate".to_string(), Compatibility::Compatible),
+            ("+ JobInfo.status".to_string(), Compatibility::Risky),
+            ("~ job-set.speed: int -> Speed".to_string(), Compatibility::Compatible),
+            ("+ JOB_STATUS.status".to_string(), Compatibility::Risky),
+            ("+ struct InlineOptions".to_string(), Compatibility::Compatible),
+            ("+ alternate Speed".to_string(), Compatibility::Compatible),
+            ("+ event JOB_DONE".to_string(), Compatibility::Risky),
+        ]
+    );
+    assert!(old.is_compatible_with(&new));
+}
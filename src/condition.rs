@@ -0,0 +1,345 @@
+extern crate serde_json;
+
+use std::collections::HashSet;
+
+use self::serde_json::{Map, Value};
+
+use schema::Schema;
+use {QemuType, Section};
+
+/// A parsed 'if' condition.  Older schemas write conditions as C
+/// preprocessor expressions like 'defined(CONFIG_SPICE)' or a list of them
+/// that all have to hold.  Newer ones use the bare symbol and
+/// {'all': [...]}, {'any': [...]} and {'not': ...}.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Condition {
+    Defined(String),
+    All(Vec<Condition>),
+    Any(Vec<Condition>),
+    Not(Box<Condition>),
+}
+
+impl Condition {
+    pub fn parse(input: &Value) -> Result<Condition, String> {
+        match input {
+            Value::String(s) => Expression::parse(s),
+            Value::Array(a) => Ok(Condition::All(
+                a.iter()
+                    .map(Condition::parse)
+                    .collect::<Result<Vec<Condition>, String>>()?,
+            )),
+            Value::Object(o) if o.len() == 1 => {
+                let (key, value) = o.iter().next().unwrap();
+                let list = |value: &Value| match value {
+                    Value::Array(a) => a
+                        .iter()
+                        .map(Condition::parse)
+                        .collect::<Result<Vec<Condition>, String>>(),
+                    _ => Err(format!("Expected a list of conditions: {:?}", value)),
+                };
+                match key.as_ref() {
+                    "all" => Ok(Condition::All(list(value)?)),
+                    "any" => Ok(Condition::Any(list(value)?)),
+                    "not" => Ok(Condition::Not(Box::new(Condition::parse(value)?))),
+                    _ => Err(format!("Unknown condition: {:?}", input)),
+                }
+            }
+            _ => Err(format!("Unknown condition: {:?}", input)),
+        }
+    }
+
+    pub fn eval(&self, defined: &HashSet<String>) -> bool {
+        match self {
+            Condition::Defined(symbol) => defined.contains(symbol),
+            Condition::All(c) => c.iter().all(|c| c.eval(defined)),
+            Condition::Any(c) => c.iter().any(|c| c.eval(defined)),
+            Condition::Not(c) => !c.eval(defined),
+        }
+    }
+}
+
+// A C preprocessor expression the way older schemas write conditions, like
+// 'defined(CONFIG_A) && !defined(CONFIG_B)'.  || binds looser than &&.
+struct Expression<'a> {
+    input: &'a str,
+    rest: &'a str,
+}
+
+impl<'a> Expression<'a> {
+    fn parse(input: &str) -> Result<Condition, String> {
+        let mut expression = Expression { input, rest: input };
+        let condition = expression.any()?;
+        if !expression.rest.trim().is_empty() {
+            return Err(expression.error());
+        }
+        Ok(condition)
+    }
+
+    fn error(&self) -> String {
+        format!("Can't parse condition: {}", self.input)
+    }
+
+    // Skip whitespace, then the token if it comes next
+    fn eat(&mut self, token: &str) -> bool {
+        self.rest = self.rest.trim_start();
+        match self.rest.strip_prefix(token) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn any(&mut self) -> Result<Condition, String> {
+        let mut conditions = vec![self.all()?];
+        while self.eat("||") {
+            conditions.push(self.all()?);
+        }
+        Ok(match conditions.len() {
+            1 => conditions.pop().unwrap(),
+            _ => Condition::Any(conditions),
+        })
+    }
+
+    fn all(&mut self) -> Result<Condition, String> {
+        let mut conditions = vec![self.unary()?];
+        while self.eat("&&") {
+            conditions.push(self.unary()?);
+        }
+        Ok(match conditions.len() {
+            1 => conditions.pop().unwrap(),
+            _ => Condition::All(conditions),
+        })
+    }
+
+    fn unary(&mut self) -> Result<Condition, String> {
+        if self.eat("!") {
+            return Ok(Condition::Not(Box::new(self.unary()?)));
+        }
+        if self.eat("(") {
+            let condition = self.any()?;
+            if !self.eat(")") {
+                return Err(self.error());
+            }
+            return Ok(condition);
+        }
+        let symbol = self.symbol()?;
+        if symbol != "defined" {
+            return Ok(Condition::Defined(symbol));
+        }
+        if !self.eat("(") {
+            return Err(self.error());
+        }
+        let symbol = self.symbol()?;
+        if !self.eat(")") {
+            return Err(self.error());
+        }
+        Ok(Condition::Defined(symbol))
+    }
+
+    fn symbol(&mut self) -> Result<String, String> {
+        self.rest = self.rest.trim_start();
+        let end = self
+            .rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(self.rest.len());
+        if end == 0 {
+            return Err(self.error());
+        }
+        let (symbol, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        Ok(symbol.to_string())
+    }
+}
+
+// A missing condition always holds
+fn holds(condition: &Value, defined: &HashSet<String>) -> Result<bool, String> {
+    if condition.is_null() {
+        return Ok(true);
+    }
+    Ok(Condition::parse(condition)?.eval(defined))
+}
+
+// Drop the members, branches, features and enum values whose condition
+// doesn't hold, and strip the condition from the ones that stay
+fn resolve_members(input: &Value, defined: &HashSet<String>) -> Result<Value, String> {
+    match input {
+        Value::Object(o) => {
+            let mut members = Map::new();
+            for (name, member) in o {
+                if let Some(m) = resolve_member(member, defined)? {
+                    members.insert(name.clone(), m);
+                }
+            }
+            Ok(Value::Object(members))
+        }
+        Value::Array(a) => {
+            let mut values = Vec::new();
+            for value in a {
+                if let Some(v) = resolve_member(value, defined)? {
+                    values.push(v);
+                }
+            }
+            Ok(Value::Array(values))
+        }
+        _ => Ok(input.clone()),
+    }
+}
+
+fn resolve_member(member: &Value, defined: &HashSet<String>) -> Result<Option<Value>, String> {
+    match member {
+        Value::Object(o) if o.contains_key("if") || o.contains_key("features") => {
+            if !holds(o.get("if").unwrap_or(&Value::Null), defined)? {
+                return Ok(None);
+            }
+            let mut m = o.clone();
+            m.remove("if");
+            if let Some(features) = o.get("features") {
+                m.insert("features".to_string(), resolve_members(features, defined)?);
+            }
+            Ok(Some(Value::Object(m)))
+        }
+        _ => Ok(Some(member.clone())),
+    }
+}
+
+fn resolve(qemu_type: &QemuType, defined: &HashSet<String>) -> Result<Option<QemuType>, String> {
+    let condition = match qemu_type {
+        QemuType::Struct(s) => &s.condition,
+        QemuType::Command(c) => &c.condition,
+        QemuType::Enum(e) => &e.condition,
+        QemuType::Event(e) => &e.condition,
+        QemuType::Union(u) => &u.condition,
+        QemuType::Alternate(a) => &a.condition,
+        _ => return Ok(Some(qemu_type.clone())),
+    };
+    if !holds(condition, defined)? {
+        return Ok(None);
+    }
+
+    let mut resolved = qemu_type.clone();
+    match resolved {
+        QemuType::Struct(ref mut s) => {
+            s.fields = resolve_members(&s.fields, defined)?;
+            s.base = resolve_members(&s.base, defined)?;
+            s.features = resolve_members(&s.features, defined)?;
+            s.condition = Value::Null;
+        }
+        QemuType::Command(ref mut c) => {
+            c.fields = resolve_members(&c.fields, defined)?;
+            c.features = resolve_members(&c.features, defined)?;
+            c.condition = Value::Null;
+        }
+        QemuType::Enum(ref mut e) => {
+            e.fields = resolve_members(&e.fields, defined)?;
+            e.features = resolve_members(&e.features, defined)?;
+            e.condition = Value::Null;
+        }
+        QemuType::Event(ref mut e) => {
+            e.data = resolve_members(&e.data, defined)?;
+            e.features = resolve_members(&e.features, defined)?;
+            e.condition = Value::Null;
+        }
+        QemuType::Union(ref mut u) => {
+            u.base = resolve_members(&u.base, defined)?;
+            u.data = resolve_members(&u.data, defined)?;
+            u.features = resolve_members(&u.features, defined)?;
+            u.condition = Value::Null;
+        }
+        QemuType::Alternate(ref mut a) => {
+            a.data = resolve_members(&a.data, defined)?;
+            a.features = resolve_members(&a.features, defined)?;
+            a.condition = Value::Null;
+        }
+        _ => {}
+    }
+    Ok(Some(resolved))
+}
+
+impl Schema {
+    /// The schema a QEMU built with the given CONFIG_ symbols would expose.
+    /// Definitions, members, branches and enum values whose 'if' condition
+    /// doesn't hold are dropped, and the rest lose their condition.
+    pub fn configure(&self, defined: &[&str]) -> Result<Schema, String> {
+        let defined: HashSet<String> = defined.iter().map(|s| s.to_string()).collect();
+        let mut sections: Vec<Section> = Vec::new();
+        for section in &self.sections {
+            if let Some(qemu_type) = resolve(&section.qemu_type, &defined)? {
+                sections.push(Section {
+                    description: section.description.clone(),
+                    qemu_type,
+                    module: section.module.clone(),
+                });
+            }
+        }
+        Ok(Schema::from_sections(sections))
+    }
+}
+
+#[test]
+fn test_parse_condition() {
+    let defined: HashSet<String> = vec!["CONFIG_SPICE".to_string()].into_iter().collect();
+    let old = Condition::parse(&Value::String("defined(CONFIG_SPICE)".to_string())).unwrap();
+    assert_eq!(old, Condition::Defined("CONFIG_SPICE".to_string()));
+    assert!(old.eval(&defined));
+
+    let new: Value =
+        serde_json::from_str(r#"{"any": ["CONFIG_VNC", {"not": "CONFIG_SPICE"}]}"#).unwrap();
+    assert!(!Condition::parse(&new).unwrap().eval(&defined));
+    assert!(Condition::parse(&serde_json::from_str(r#"{"bogus": []}"#).unwrap()).is_err());
+
+    let defined_symbol = |s: &str| Condition::Defined(s.to_string());
+    let parse = |s: &str| Condition::parse(&Value::String(s.to_string()));
+    assert_eq!(
+        parse("defined(CONFIG_SPICE) && defined(CONFIG_VNC)").unwrap(),
+        Condition::All(vec![defined_symbol("CONFIG_SPICE"), defined_symbol("CONFIG_VNC")])
+    );
+    assert_eq!(
+        parse("!defined(CONFIG_VNC) && (CONFIG_SPICE || defined(CONFIG_OPENGL))").unwrap(),
+        Condition::All(vec![
+            Condition::Not(Box::new(defined_symbol("CONFIG_VNC"))),
+            Condition::Any(vec![defined_symbol("CONFIG_SPICE"), defined_symbol("CONFIG_OPENGL")]),
+        ])
+    );
+    assert!(parse("defined(CONFIG_VNC) || defined(CONFIG_SPICE)").unwrap().eval(&defined));
+    assert!(!parse("defined(CONFIG_VNC) && defined(CONFIG_SPICE)").unwrap().eval(&defined));
+    assert!(parse("defined(CONFIG_SPICE").is_err());
+    assert!(parse("CONFIG_SPICE == 1").is_err());
+    assert!(parse("").is_err());
+}
+
+#[test]
+fn test_configure() {
+    let input = r#"
+{ 'enum': 'DisplayType',
+  'data': [ 'none', { 'name': 'spice-app', 'if': 'CONFIG_SPICE' },
+            { 'name': 'vnc', 'if': 'CONFIG_VNC' } ] }
+{ 'struct': 'SpiceInfo', 'data': { 'enabled': 'bool' },
+  'if': 'defined(CONFIG_SPICE)' }
+{ 'struct': 'DisplayOptions',
+  'data': { 'type': 'DisplayType',
+            '*gl': { 'type': 'bool', 'if': { 'all': [ 'CONFIG_OPENGL', 'CONFIG_SPICE' ] } } } }
+{ 'command': 'query-spice', 'returns': 'SpiceInfo', 'if': 'CONFIG_SPICE' }
+"#;
+    let schema = Schema::parse(input).unwrap();
+
+    let spice = schema.configure(&["CONFIG_SPICE"]).unwrap();
+    assert!(spice.get("query-spice").is_some());
+    match spice.get("DisplayType") {
+        Some(QemuType::Enum(e)) => assert_eq!(
+            e.fields,
+            serde_json::from_str::<Value>(r#"["none", {"name": "spice-app"}]"#).unwrap()
+        ),
+        other => panic!("Expected an enum: {:?}", other),
+    }
+    match spice.get("DisplayOptions") {
+        Some(QemuType::Struct(s)) => assert!(s.fields.get("*gl").is_none()),
+        other => panic!("Expected a struct: {:?}", other),
+    }
+
+    let bare = schema.configure(&[]).unwrap();
+    assert!(bare.get("query-spice").is_none());
+    assert!(bare.get("SpiceInfo").is_none());
+    assert!(bare.get("DisplayOptions").is_some());
+}
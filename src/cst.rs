@@ -0,0 +1,635 @@
+extern crate serde_json;
+
+use std::fmt;
+
+use self::serde_json::{Map, Value};
+
+use schema::Schema;
+use {QemuType, Section};
+
+/// Whitespace and comments, kept so the source can be written back out
+/// exactly as it was read
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Trivia {
+    Whitespace(String),
+    /// A comment line starting with '#', without its newline
+    Comment(String),
+}
+
+impl Trivia {
+    fn text(&self) -> &str {
+        match self {
+            Trivia::Whitespace(s) => s,
+            Trivia::Comment(s) => s,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TokenKind {
+    LeftBrace,
+    RightBrace,
+    LeftBracket,
+    RightBracket,
+    Colon,
+    Comma,
+    /// A quoted string, either 'single' or "double" quoted
+    String,
+    /// true, false and anything else that isn't quoted
+    Word,
+}
+
+/// A token along with the whitespace and comments in front of it
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Token {
+    pub leading: Vec<Trivia>,
+    pub kind: TokenKind,
+    /// The token exactly as written, quotes included
+    pub text: String,
+}
+
+impl Token {
+    pub fn new(kind: TokenKind, text: &str) -> Token {
+        Token {
+            leading: Vec::new(),
+            kind,
+            text: text.to_string(),
+        }
+    }
+
+    /// A string token quoted the way QAPI schemas usually are
+    pub fn string(value: &str) -> Token {
+        Token::new(
+            TokenKind::String,
+            &format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'")),
+        )
+    }
+
+    /// The value of a string token without quotes and escapes
+    pub fn unquoted(&self) -> String {
+        let inner = &self.text[1..self.text.len() - 1];
+        let mut value = String::new();
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => value.extend(chars.next()),
+                _ => value.push(c),
+            }
+        }
+        value
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for trivia in &self.leading {
+            f.write_str(trivia.text())?;
+        }
+        f.write_str(&self.text)
+    }
+}
+
+/// A `'key': value` pair of an object
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Member {
+    pub key: Token,
+    pub colon: Token,
+    pub value: Node,
+    pub comma: Option<Token>,
+}
+
+/// An element of an array
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Element {
+    pub value: Node,
+    pub comma: Option<Token>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Node {
+    Scalar(Token),
+    Object {
+        open: Token,
+        members: Vec<Member>,
+        close: Token,
+    },
+    Array {
+        open: Token,
+        elements: Vec<Element>,
+        close: Token,
+    },
+}
+
+impl Node {
+    /// The first token of the node, which holds the trivia in front of it
+    pub fn first_token(&self) -> &Token {
+        match self {
+            Node::Scalar(t) => t,
+            Node::Object { open, .. } => open,
+            Node::Array { open, .. } => open,
+        }
+    }
+
+    pub fn first_token_mut(&mut self) -> &mut Token {
+        match *self {
+            Node::Scalar(ref mut t) => t,
+            Node::Object { ref mut open, .. } => open,
+            Node::Array { ref mut open, .. } => open,
+        }
+    }
+
+    /// The value of the member with the given key, if this is an object
+    pub fn get(&self, key: &str) -> Option<&Node> {
+        match self {
+            Node::Object { members, .. } => members
+                .iter()
+                .find(|m| m.key.unquoted() == key)
+                .map(|m| &m.value),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Node> {
+        match self {
+            &mut Node::Object {
+                ref mut members, ..
+            } => members
+                .iter_mut()
+                .find(|m| m.key.unquoted() == key)
+                .map(|m| &mut m.value),
+            _ => None,
+        }
+    }
+
+    /// A node for the value, laid out on one line the way the formatter
+    /// would
+    pub fn from_value(value: &Value) -> Node {
+        let spaced = |mut token: Token| {
+            token.leading.push(Trivia::Whitespace(" ".to_string()));
+            token
+        };
+        match value {
+            Value::Object(o) => {
+                let last = o.len().saturating_sub(1);
+                Node::Object {
+                    open: Token::new(TokenKind::LeftBrace, "{"),
+                    members: o
+                        .iter()
+                        .enumerate()
+                        .map(|(i, (k, v))| {
+                            let mut value = Node::from_value(v);
+                            value
+                                .first_token_mut()
+                                .leading
+                                .push(Trivia::Whitespace(" ".to_string()));
+                            Member {
+                                key: spaced(Token::string(k)),
+                                colon: Token::new(TokenKind::Colon, ":"),
+                                value,
+                                comma: if i < last {
+                                    Some(Token::new(TokenKind::Comma, ","))
+                                } else {
+                                    None
+                                },
+                            }
+                        })
+                        .collect(),
+                    close: if o.is_empty() {
+                        Token::new(TokenKind::RightBrace, "}")
+                    } else {
+                        spaced(Token::new(TokenKind::RightBrace, "}"))
+                    },
+                }
+            }
+            // Array types like ['int'] are written without the spaces
+            Value::Array(a) => {
+                let tight = a.len() < 2 && a.iter().all(|v| v.is_string());
+                let last = a.len().saturating_sub(1);
+                Node::Array {
+                    open: Token::new(TokenKind::LeftBracket, "["),
+                    elements: a
+                        .iter()
+                        .enumerate()
+                        .map(|(i, v)| {
+                            let mut value = Node::from_value(v);
+                            if !tight {
+                                value
+                                    .first_token_mut()
+                                    .leading
+                                    .push(Trivia::Whitespace(" ".to_string()));
+                            }
+                            Element {
+                                value,
+                                comma: if i < last {
+                                    Some(Token::new(TokenKind::Comma, ","))
+                                } else {
+                                    None
+                                },
+                            }
+                        })
+                        .collect(),
+                    close: if tight {
+                        Token::new(TokenKind::RightBracket, "]")
+                    } else {
+                        spaced(Token::new(TokenKind::RightBracket, "]"))
+                    },
+                }
+            }
+            Value::String(s) => Node::Scalar(Token::string(s)),
+            &Value::Bool(b) => Node::Scalar(Token::new(TokenKind::Word, &b.to_string())),
+            _ => Node::Scalar(Token::new(TokenKind::Word, &value.to_string())),
+        }
+    }
+
+    /// The doc comment in front of the node, one entry per line without
+    /// the leading '#', the same way `Section::description` has it.  Only
+    /// the last block of comments counts, blank lines separate blocks.
+    pub fn doc(&self) -> Vec<String> {
+        let mut doc: Vec<String> = Vec::new();
+        let mut blank = false;
+        for trivia in &self.first_token().leading {
+            match trivia {
+                Trivia::Comment(c) => {
+                    if blank {
+                        doc.clear();
+                        blank = false;
+                    }
+                    doc.push(c[1..].to_string())
+                }
+                Trivia::Whitespace(w) => blank = blank || w.matches('\n').count() > 1,
+            }
+        }
+        doc
+    }
+
+    pub fn to_value(&self) -> Result<Value, String> {
+        match self {
+            Node::Scalar(t) => match t.kind {
+                TokenKind::String => Ok(Value::String(t.unquoted())),
+                _ => match t.text.as_ref() {
+                    "true" => Ok(Value::Bool(true)),
+                    "false" => Ok(Value::Bool(false)),
+                    _ => Err(format!("Unexpected {} in schema", t.text)),
+                },
+            },
+            Node::Object { members, .. } => {
+                let mut map = Map::new();
+                for member in members {
+                    map.insert(member.key.unquoted(), member.value.to_value()?);
+                }
+                Ok(Value::Object(map))
+            }
+            Node::Array { elements, .. } => Ok(Value::Array(
+                elements
+                    .iter()
+                    .map(|e| e.value.to_value())
+                    .collect::<Result<Vec<Value>, String>>()?,
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Node::Scalar(t) => write!(f, "{}", t),
+            Node::Object {
+                open,
+                members,
+                close,
+            } => {
+                write!(f, "{}", open)?;
+                for member in members {
+                    write!(f, "{}{}{}", member.key, member.colon, member.value)?;
+                    if let Some(ref comma) = member.comma {
+                        write!(f, "{}", comma)?;
+                    }
+                }
+                write!(f, "{}", close)
+            }
+            Node::Array {
+                open,
+                elements,
+                close,
+            } => {
+                write!(f, "{}", open)?;
+                for element in elements {
+                    write!(f, "{}", element.value)?;
+                    if let Some(ref comma) = element.comma {
+                        write!(f, "{}", comma)?;
+                    }
+                }
+                write!(f, "{}", close)
+            }
+        }
+    }
+}
+
+/// A QAPI schema file with every token, comment and bit of whitespace
+/// kept.  Displaying it gives back the source byte for byte.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SyntaxTree {
+    /// The top level expressions, each carrying the comments in front of it
+    pub definitions: Vec<Node>,
+    /// Whatever comes after the last definition
+    pub trailing: Vec<Trivia>,
+}
+
+// Splits the source into tokens, each with the trivia in front of it
+struct Lexer<'a> {
+    input: &'a str,
+    pos: usize,
+    line: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn take_while<F: Fn(char) -> bool>(&mut self, f: F) -> &'a str {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if !f(c) {
+                break;
+            }
+            if c == '\n' {
+                self.line += 1;
+            }
+            self.pos += c.len_utf8();
+        }
+        &self.input[start..self.pos]
+    }
+
+    fn trivia(&mut self) -> Vec<Trivia> {
+        let mut trivia = Vec::new();
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() => trivia.push(Trivia::Whitespace(
+                    self.take_while(|c| c.is_whitespace()).to_string(),
+                )),
+                Some('#') => {
+                    trivia.push(Trivia::Comment(self.take_while(|c| c != '\n').to_string()))
+                }
+                _ => return trivia,
+            }
+        }
+    }
+
+    fn string(&mut self, quote: char) -> Result<&'a str, String> {
+        let start = self.pos;
+        let line = self.line;
+        self.pos += 1;
+        let mut escaped = false;
+        while let Some(c) = self.peek() {
+            self.pos += c.len_utf8();
+            match c {
+                '\n' => break,
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                _ if c == quote => return Ok(&self.input[start..self.pos]),
+                _ => {}
+            }
+        }
+        Err(format!("Line {}: unterminated string", line))
+    }
+
+    // None at the end of the input, with the trivia that is left
+    fn next(&mut self) -> Result<Result<Token, Vec<Trivia>>, String> {
+        let leading = self.trivia();
+        let c = match self.peek() {
+            Some(c) => c,
+            None => return Ok(Err(leading)),
+        };
+        let (kind, text) = match c {
+            '{' => (TokenKind::LeftBrace, &self.input[self.pos..self.pos + 1]),
+            '}' => (TokenKind::RightBrace, &self.input[self.pos..self.pos + 1]),
+            '[' => (TokenKind::LeftBracket, &self.input[self.pos..self.pos + 1]),
+            ']' => (TokenKind::RightBracket, &self.input[self.pos..self.pos + 1]),
+            ':' => (TokenKind::Colon, &self.input[self.pos..self.pos + 1]),
+            ',' => (TokenKind::Comma, &self.input[self.pos..self.pos + 1]),
+            '\'' | '"' => (TokenKind::String, self.string(c)?),
+            c if c.is_alphanumeric() => (
+                TokenKind::Word,
+                self.take_while(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.'),
+            ),
+            c => return Err(format!("Line {}: unexpected '{}'", self.line, c)),
+        };
+        if kind != TokenKind::String && kind != TokenKind::Word {
+            self.pos += 1;
+        }
+        Ok(Ok(Token {
+            leading,
+            kind,
+            text: text.to_string(),
+        }))
+    }
+}
+
+struct Parser<'a> {
+    lexer: Lexer<'a>,
+    // One token of lookahead, or the trivia at the end of the input
+    next: Result<Token, Vec<Trivia>>,
+}
+
+impl<'a> Parser<'a> {
+    fn advance(&mut self) -> Result<Token, String> {
+        let next = self.lexer.next()?;
+        match ::std::mem::replace(&mut self.next, next) {
+            Ok(token) => Ok(token),
+            Err(_) => Err(format!("Line {}: unexpected end of input", self.lexer.line)),
+        }
+    }
+
+    fn peek(&self) -> Option<TokenKind> {
+        self.next.as_ref().ok().map(|t| t.kind)
+    }
+
+    fn expect(&mut self, kind: TokenKind) -> Result<Token, String> {
+        let token = self.advance()?;
+        if token.kind != kind {
+            return Err(format!(
+                "Line {}: expected {:?}, found {}",
+                self.lexer.line, kind, token.text
+            ));
+        }
+        Ok(token)
+    }
+
+    fn comma(&mut self) -> Result<Option<Token>, String> {
+        match self.peek() {
+            Some(TokenKind::Comma) => Ok(Some(self.advance()?)),
+            _ => Ok(None),
+        }
+    }
+
+    fn node(&mut self) -> Result<Node, String> {
+        let open = self.advance()?;
+        match open.kind {
+            TokenKind::LeftBrace => {
+                let mut members = Vec::new();
+                while self.peek() != Some(TokenKind::RightBrace) {
+                    let key = self.expect(TokenKind::String)?;
+                    let colon = self.expect(TokenKind::Colon)?;
+                    let value = self.node()?;
+                    let comma = self.comma()?;
+                    let last = comma.is_none();
+                    members.push(Member {
+                        key,
+                        colon,
+                        value,
+                        comma,
+                    });
+                    if last {
+                        break;
+                    }
+                }
+                let close = self.expect(TokenKind::RightBrace)?;
+                Ok(Node::Object {
+                    open,
+                    members,
+                    close,
+                })
+            }
+            TokenKind::LeftBracket => {
+                let mut elements = Vec::new();
+                while self.peek() != Some(TokenKind::RightBracket) {
+                    let value = self.node()?;
+                    let comma = self.comma()?;
+                    let last = comma.is_none();
+                    elements.push(Element { value, comma });
+                    if last {
+                        break;
+                    }
+                }
+                let close = self.expect(TokenKind::RightBracket)?;
+                Ok(Node::Array {
+                    open,
+                    elements,
+                    close,
+                })
+            }
+            TokenKind::String | TokenKind::Word => Ok(Node::Scalar(open)),
+            _ => Err(format!(
+                "Line {}: unexpected {}",
+                self.lexer.line, open.text
+            )),
+        }
+    }
+}
+
+impl SyntaxTree {
+    pub fn parse(input: &str) -> Result<SyntaxTree, String> {
+        let mut lexer = Lexer {
+            input,
+            pos: 0,
+            line: 1,
+        };
+        let next = lexer.next()?;
+        let mut parser = Parser { lexer, next };
+        let mut definitions = Vec::new();
+        while parser.next.is_ok() {
+            let definition = parser.node()?;
+            match definition {
+                Node::Object { .. } => definitions.push(definition),
+                _ => {
+                    return Err(format!(
+                        "Line {}: top level expressions have to be objects",
+                        parser.lexer.line
+                    ))
+                }
+            }
+        }
+        let trailing = match parser.next {
+            Err(trivia) => trivia,
+            Ok(_) => Vec::new(),
+        };
+        Ok(SyntaxTree {
+            definitions,
+            trailing,
+        })
+    }
+
+    /// The sections of the schema, with the doc comment in front of each
+    /// definition as its description
+    pub fn sections(&self) -> Result<Vec<Section>, String> {
+        self.definitions
+            .iter()
+            .map(|d| {
+                Ok(Section {
+                    description: d.doc(),
+                    qemu_type: QemuType::parse(d.to_value()?)?,
+                    module: None,
+                })
+            })
+            .collect()
+    }
+
+    pub fn to_schema(&self) -> Result<Schema, String> {
+        Ok(Schema::from_sections(self.sections()?))
+    }
+}
+
+impl fmt::Display for SyntaxTree {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for definition in &self.definitions {
+            write!(f, "{}", definition)?;
+        }
+        for trivia in &self.trailing {
+            f.write_str(trivia.text())?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_syntax_tree() {
+    let input = r#"# -*- Mode: Python -*-
+
+##
+# @StatusInfo:
+#
+# Information about VCPU run state
+##
+{ 'struct': 'StatusInfo',
+  'data': {'running': 'bool',  # whether the guest runs
+           "singlestep": 'bool', 'status': 'RunState'} }
+
+{ 'enum': 'RunState', 'data': [ 'debug', 'it\'s', ] }
+# the end
+"#;
+    let tree = SyntaxTree::parse(input).unwrap();
+    assert_eq!(tree.to_string(), input);
+    assert_eq!(tree.definitions.len(), 2);
+    assert_eq!(
+        tree.definitions[0].doc(),
+        vec![
+            "#",
+            " @StatusInfo:",
+            "",
+            " Information about VCPU run state",
+            "#"
+        ]
+    );
+    assert!(tree.definitions[1].doc().is_empty());
+
+    let schema = tree.to_schema().unwrap();
+    match schema.get("StatusInfo") {
+        Some(QemuType::Struct(s)) => assert_eq!(
+            s.fields,
+            serde_json::from_str::<Value>(
+                r#"{"running": "bool", "singlestep": "bool", "status": "RunState"}"#
+            )
+            .unwrap()
+        ),
+        other => panic!("Expected a struct: {:?}", other),
+    }
+    match schema.get("RunState") {
+        Some(QemuType::Enum(e)) => assert_eq!(e.fields[1], Value::String("it's".to_string())),
+        other => panic!("Expected an enum: {:?}", other),
+    }
+    assert_eq!(schema.sections[0].doc().name, "StatusInfo".to_string());
+
+    assert!(SyntaxTree::parse("{ 'struct': 'A' ").is_err());
+    assert!(SyntaxTree::parse("{ 'struct': 'A' } @").is_err());
+}
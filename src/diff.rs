@@ -0,0 +1,512 @@
+extern crate serde;
+extern crate serde_json;
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use self::serde::{Deserialize, Serialize};
+use self::serde_json::{Map, Value};
+
+use schema::{member_type, names, Schema};
+use QemuType;
+
+/// One difference between two versions of a schema.  Members are named
+/// without the optional marker.  Branches of unions and alternates are
+/// reported as members too.
+///
+/// Changes serialize to JSON objects with the variant in a "change" key,
+/// like `{"change": "member-added", "definition": ..., "member": ...}`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "change", rename_all = "kebab-case")]
+pub enum Change {
+    Added {
+        kind: String,
+        name: String,
+    },
+    Removed {
+        kind: String,
+        name: String,
+    },
+    KindChanged {
+        name: String,
+        old: String,
+        new: String,
+    },
+    BaseChanged {
+        definition: String,
+        old: String,
+        new: String,
+    },
+    /// The discriminator of a union changed.  A simple union has none, which
+    /// shows as an empty name.
+    DiscriminatorChanged {
+        definition: String,
+        old: String,
+        new: String,
+    },
+    MemberAdded {
+        definition: String,
+        member: String,
+        optional: bool,
+    },
+    MemberRemoved {
+        definition: String,
+        member: String,
+    },
+    MadeMandatory {
+        definition: String,
+        member: String,
+    },
+    MadeOptional {
+        definition: String,
+        member: String,
+    },
+    /// The type of a member changed.  Without a member the arguments of a
+    /// command or the data of an event switched to a different named type.
+    TypeChanged {
+        definition: String,
+        member: Option<String>,
+        old: String,
+        new: String,
+    },
+    ReturnsChanged {
+        command: String,
+        old: String,
+        new: String,
+    },
+    EnumValueAdded {
+        definition: String,
+        value: String,
+    },
+    EnumValueRemoved {
+        definition: String,
+        value: String,
+    },
+    FeatureAdded {
+        definition: String,
+        member: Option<String>,
+        feature: String,
+    },
+    FeatureRemoved {
+        definition: String,
+        member: Option<String>,
+        feature: String,
+    },
+}
+
+fn qualified(definition: &str, member: &Option<String>) -> String {
+    match member {
+        Some(m) => format!("{}.{}", definition, m),
+        None => definition.to_string(),
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Change::Added { kind, name } => write!(f, "+ {} {}", kind, name),
+            Change::Removed { kind, name } => write!(f, "- {} {}", kind, name),
+            Change::KindChanged { name, old, new } => write!(f, "~ {}: {} -> {}", name, old, new),
+            Change::BaseChanged {
+                definition,
+                old,
+                new,
+            } => write!(f, "~ {} base: {} -> {}", definition, old, new),
+            Change::DiscriminatorChanged {
+                definition,
+                old,
+                new,
+            } => write!(f, "~ {} discriminator: {} -> {}", definition, old, new),
+            Change::MemberAdded {
+                definition,
+                member,
+                optional,
+            } => {
+                let marker = if *optional { "*" } else { "" };
+                write!(f, "+ {}.{}{}", definition, marker, member)
+            }
+            Change::MemberRemoved {
+                definition,
+                member,
+            } => write!(f, "- {}.{}", definition, member),
+            Change::MadeMandatory {
+                definition,
+                member,
+            } => write!(f, "~ {}.{}: optional -> mandatory", definition, member),
+            Change::MadeOptional {
+                definition,
+                member,
+            } => write!(f, "~ {}.{}: mandatory -> optional", definition, member),
+            Change::TypeChanged {
+                definition,
+                member,
+                old,
+                new,
+            } => write!(f, "~ {}: {} -> {}", qualified(definition, member), old, new),
+            Change::ReturnsChanged {
+                command,
+                old,
+                new,
+            } => write!(f, "~ {} returns: {} -> {}", command, old, new),
+            Change::EnumValueAdded {
+                definition,
+                value,
+            } => write!(f, "+ {} value {}", definition, value),
+            Change::EnumValueRemoved {
+                definition,
+                value,
+            } => write!(f, "- {} value {}", definition, value),
+            Change::FeatureAdded {
+                definition,
+                member,
+                feature,
+            } => write!(f, "+ {} feature {}", qualified(definition, member), feature),
+            Change::FeatureRemoved {
+                definition,
+                member,
+                feature,
+            } => write!(f, "- {} feature {}", qualified(definition, member), feature),
+        }
+    }
+}
+
+// A type reference the way it's written in the schema, with arrays as [T]
+pub fn type_string(input: &Value) -> String {
+    match input {
+        Value::Null => "".to_string(),
+        Value::String(s) => s.clone(),
+        Value::Array(a) => format!(
+            "[{}]",
+            a.iter()
+                .map(type_string)
+                .collect::<Vec<String>>()
+                .join(", ")
+        ),
+        Value::Object(_) => "{...}".to_string(),
+        _ => input.to_string(),
+    }
+}
+
+struct Member<'a> {
+    optional: bool,
+    type_name: String,
+    features: Vec<&'a str>,
+}
+
+fn members<'a>(input: &'a Value) -> BTreeMap<String, Member<'a>> {
+    let mut members = BTreeMap::new();
+    if let Value::Object(o) = input {
+        for (name, value) in o {
+            let features = match value {
+                Value::Object(m) => names(m.get("features").unwrap_or(&Value::Null)),
+                _ => Vec::new(),
+            };
+            members.insert(
+                name.trim_start_matches('*').to_string(),
+                Member {
+                    optional: name.starts_with('*'),
+                    type_name: type_string(member_type(value)),
+                    features,
+                },
+            );
+        }
+    }
+    members
+}
+
+fn diff_features(
+    definition: &str,
+    member: Option<&str>,
+    old: &[&str],
+    new: &[&str],
+    changes: &mut Vec<Change>,
+) {
+    for feature in old.iter().filter(|f| !new.contains(f)) {
+        changes.push(Change::FeatureRemoved {
+            definition: definition.to_string(),
+            member: member.map(|m| m.to_string()),
+            feature: feature.to_string(),
+        });
+    }
+    for feature in new.iter().filter(|f| !old.contains(f)) {
+        changes.push(Change::FeatureAdded {
+            definition: definition.to_string(),
+            member: member.map(|m| m.to_string()),
+            feature: feature.to_string(),
+        });
+    }
+}
+
+fn diff_members(definition: &str, old: &Value, new: &Value, changes: &mut Vec<Change>) {
+    // No members at all is the same as an empty set of them
+    let empty = Value::Object(Map::new());
+    let old = if old.is_null() { &empty } else { old };
+    let new = if new.is_null() { &empty } else { new };
+
+    // A named type on either side can't be compared member by member
+    if !old.is_object() || !new.is_object() {
+        if old != new {
+            changes.push(Change::TypeChanged {
+                definition: definition.to_string(),
+                member: None,
+                old: type_string(old),
+                new: type_string(new),
+            });
+        }
+        return;
+    }
+
+    let old = members(old);
+    let new = members(new);
+    for (name, o) in &old {
+        let n = match new.get(name) {
+            Some(n) => n,
+            None => {
+                changes.push(Change::MemberRemoved {
+                    definition: definition.to_string(),
+                    member: name.clone(),
+                });
+                continue;
+            }
+        };
+        if o.optional && !n.optional {
+            changes.push(Change::MadeMandatory {
+                definition: definition.to_string(),
+                member: name.clone(),
+            });
+        } else if !o.optional && n.optional {
+            changes.push(Change::MadeOptional {
+                definition: definition.to_string(),
+                member: name.clone(),
+            });
+        }
+        if o.type_name != n.type_name {
+            changes.push(Change::TypeChanged {
+                definition: definition.to_string(),
+                member: Some(name.clone()),
+                old: o.type_name.clone(),
+                new: n.type_name.clone(),
+            });
+        }
+        diff_features(definition, Some(name), &o.features, &n.features, changes);
+    }
+    for (name, n) in &new {
+        if !old.contains_key(name) {
+            changes.push(Change::MemberAdded {
+                definition: definition.to_string(),
+                member: name.clone(),
+                optional: n.optional,
+            });
+        }
+    }
+}
+
+fn diff_base(definition: &str, old: &Value, new: &Value, changes: &mut Vec<Change>) {
+    if old.is_object() && new.is_object() {
+        diff_members(definition, old, new, changes);
+    } else if old != new {
+        changes.push(Change::BaseChanged {
+            definition: definition.to_string(),
+            old: type_string(old),
+            new: type_string(new),
+        });
+    }
+}
+
+fn diff_enum(definition: &str, old: &Value, new: &Value, changes: &mut Vec<Change>) {
+    let old_values = names(old);
+    let new_values = names(new);
+    for value in old_values.iter().filter(|v| !new_values.contains(v)) {
+        changes.push(Change::EnumValueRemoved {
+            definition: definition.to_string(),
+            value: value.to_string(),
+        });
+    }
+    for value in new_values.iter().filter(|v| !old_values.contains(v)) {
+        changes.push(Change::EnumValueAdded {
+            definition: definition.to_string(),
+            value: value.to_string(),
+        });
+    }
+    // Features of the values that are in both
+    let value_features = |list: &Value, value: &str| -> Vec<String> {
+        list.as_array()
+            .and_then(|a| {
+                a.iter()
+                    .find(|v| v.get("name").and_then(|n| n.as_str()) == Some(value))
+            })
+            .map(|v| {
+                names(&v["features"])
+                    .into_iter()
+                    .map(|f| f.to_string())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    for value in old_values.iter().filter(|v| new_values.contains(v)) {
+        let o = value_features(old, value);
+        let n = value_features(new, value);
+        let o: Vec<&str> = o.iter().map(|f| f.as_str()).collect();
+        let n: Vec<&str> = n.iter().map(|f| f.as_str()).collect();
+        diff_features(definition, Some(value), &o, &n, changes);
+    }
+}
+
+fn features(qemu_type: &QemuType) -> Vec<&str> {
+    match qemu_type {
+        QemuType::Struct(s) => names(&s.features),
+        QemuType::Command(c) => names(&c.features),
+        QemuType::Enum(e) => names(&e.features),
+        QemuType::Event(e) => names(&e.features),
+        QemuType::Union(u) => names(&u.features),
+        QemuType::Alternate(a) => names(&a.features),
+        _ => Vec::new(),
+    }
+}
+
+fn diff_definition(old: &QemuType, new: &QemuType, changes: &mut Vec<Change>) {
+    match (old, new) {
+        (QemuType::Struct(o), QemuType::Struct(n)) => {
+            diff_base(&n.name, &o.base, &n.base, changes);
+            diff_members(&n.name, &o.fields, &n.fields, changes);
+        }
+        (QemuType::Union(o), QemuType::Union(n)) => {
+            diff_base(&n.name, &o.base, &n.base, changes);
+            if o.discriminator != n.discriminator {
+                changes.push(Change::DiscriminatorChanged {
+                    definition: n.name.clone(),
+                    old: type_string(&o.discriminator),
+                    new: type_string(&n.discriminator),
+                });
+            }
+            diff_members(&n.name, &o.data, &n.data, changes);
+        }
+        (QemuType::Alternate(o), QemuType::Alternate(n)) => {
+            diff_members(&n.name, &o.data, &n.data, changes);
+        }
+        (QemuType::Command(o), QemuType::Command(n)) => {
+            diff_members(&n.name, &o.fields, &n.fields, changes);
+            if o.returns != n.returns {
+                changes.push(Change::ReturnsChanged {
+                    command: n.name.clone(),
+                    old: type_string(&o.returns),
+                    new: type_string(&n.returns),
+                });
+            }
+        }
+        (QemuType::Event(o), QemuType::Event(n)) => {
+            diff_members(&n.name, &o.data, &n.data, changes);
+        }
+        (QemuType::Enum(o), QemuType::Enum(n)) => {
+            diff_enum(&n.name, &o.fields, &n.fields, changes);
+        }
+        _ => {
+            changes.push(Change::KindChanged {
+                name: new.name().unwrap_or("").to_string(),
+                old: old.kind().to_string(),
+                new: new.kind().to_string(),
+            });
+            return;
+        }
+    }
+    diff_features(
+        new.name().unwrap(),
+        None,
+        &features(old),
+        &features(new),
+        changes,
+    );
+}
+
+impl Schema {
+    /// Everything that changed going from this schema to `new`.  Changes to
+    /// existing definitions come first in the order of this schema, followed
+    /// by the definitions only `new` has.
+    pub fn diff(&self, new: &Schema) -> Vec<Change> {
+        let mut changes: Vec<Change> = Vec::new();
+        for old_type in self.definitions() {
+            let name = old_type.name().unwrap();
+            match new.get(name) {
+                Some(new_type) => diff_definition(old_type, new_type, &mut changes),
+                None => changes.push(Change::Removed {
+                    kind: old_type.kind().to_string(),
+                    name: name.to_string(),
+                }),
+            }
+        }
+        for new_type in new.definitions() {
+            let name = new_type.name().unwrap();
+            if self.get(name).is_none() {
+                changes.push(Change::Added {
+                    kind: new_type.kind().to_string(),
+                    name: name.to_string(),
+                });
+            }
+        }
+        changes
+    }
+}
+
+#[test]
+fn test_diff() {
+    let old = Schema::parse(
+        r#"
+{ 'enum': 'RunState', 'data': [ 'running', 'paused', 'debug' ] }
+{ 'struct': 'StatusInfo',
+  'data': { 'running': 'bool', '*singlestep': 'bool', 'status': 'RunState' } }
+{ 'command': 'query-status', 'returns': 'StatusInfo' }
+{ 'command': 'stop', 'data': { '*force': 'bool' } }
+{ 'command': 'cpu', 'data': { 'index': 'int' } }
+{ 'union': 'Options', 'base': { 'driver': 'str', 'format': 'str' },
+  'discriminator': 'driver', 'data': { 'file': 'StatusInfo' } }
+"#,
+    )
+    .unwrap();
+    let new = Schema::parse(
+        r#"
+{ 'enum': 'RunState', 'data': [ 'running', 'paused', 'colo' ] }
+{ 'struct': 'StatusInfo',
+  'data': { 'running': 'bool', 'singlestep': 'bool', 'status': 'str',
+            '*reason': 'str' },
+  'features': [ 'unstable' ] }
+{ 'command': 'query-status', 'returns': ['StatusInfo'] }
+{ 'command': 'stop', 'data': { 'force': 'bool' } }
+{ 'command': 'cont' }
+{ 'union': 'Options', 'base': { 'driver': 'str', 'format': 'str' },
+  'discriminator': 'format', 'data': { 'file': 'StatusInfo' } }
+"#,
+    )
+    .unwrap();
+
+    let changes: Vec<String> = old.diff(&new).iter().map(|c| c.to_string()).collect();
+    assert_eq!(
+        changes,
+        vec![
+            "- RunState value debug",
+            "+ RunState value colo",
+            "~ StatusInfo.singlestep: optional -> mandatory",
+            "~ StatusInfo.status: RunState -> str",
+            "+ StatusInfo.*reason",
+            "+ StatusInfo feature unstable",
+            "~ query-status returns: StatusInfo -> [StatusInfo]",
+            "~ stop.force: optional -> mandatory",
+            "- command cpu",
+            "~ Options discriminator: driver -> format",
+            "+ command cont",
+        ]
+    );
+    assert!(old.diff(&old).is_empty());
+
+    let report = serde_json::to_value(old.diff(&new)).unwrap();
+    assert_eq!(
+        report[8],
+        serde_json::from_str::<Value>(
+            r#"{"change": "removed", "kind": "command", "name": "cpu"}"#
+        )
+        .unwrap()
+    );
+    assert_eq!(
+        serde_json::from_value::<Vec<Change>>(report).unwrap(),
+        old.diff(&new)
+    );
+}
@@ -0,0 +1,545 @@
+extern crate serde_json;
+
+use self::serde_json::Value;
+
+use cst::{Element, Member, Node, SyntaxTree, Token, TokenKind, Trivia};
+
+// The keys that say what a definition is, and hold its name
+const KINDS: &[&str] = &["struct", "union", "alternate", "enum", "command", "event"];
+
+fn definition_name(node: &Node) -> Option<(&'static str, String)> {
+    KINDS.iter().find_map(|kind| match node.get(kind) {
+        Some(Node::Scalar(t)) if t.kind == TokenKind::String => Some((*kind, t.unquoted())),
+        _ => None,
+    })
+}
+
+// Replace the value of a string token, keeping the quotes it was written
+// with
+fn set_string(token: &mut Token, value: &str) {
+    let mut text = Token::string(value).text;
+    if token.text.starts_with('"') {
+        text = format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""));
+    }
+    token.text = text;
+}
+
+// New items copy the whitespace in front of the last one so they line up
+// with it, but not its comments
+fn spacing(token: &Token) -> Vec<Trivia> {
+    match token.leading.last() {
+        Some(Trivia::Whitespace(w)) => vec![Trivia::Whitespace(w.clone())],
+        _ => vec![Trivia::Whitespace(" ".to_string())],
+    }
+}
+
+fn comma() -> Option<Token> {
+    Some(Token::new(TokenKind::Comma, ","))
+}
+
+fn push_member(object: &mut Node, key: &str, value: &Value) -> Result<(), String> {
+    let members = match object {
+        &mut Node::Object {
+            ref mut members, ..
+        } => members,
+        _ => return Err("Expected an object".to_string()),
+    };
+    let mut key_token = Token::string(key);
+    let trailing_comma = match members.last_mut() {
+        Some(last) => {
+            key_token.leading = spacing(&last.key);
+            let trailing = last.comma.is_some();
+            if !trailing {
+                last.comma = comma();
+            }
+            trailing
+        }
+        None => {
+            key_token.leading = vec![Trivia::Whitespace(" ".to_string())];
+            false
+        }
+    };
+    let mut value = Node::from_value(value);
+    value.first_token_mut().leading = vec![Trivia::Whitespace(" ".to_string())];
+    members.push(Member {
+        key: key_token,
+        colon: Token::new(TokenKind::Colon, ":"),
+        value,
+        comma: if trailing_comma { comma() } else { None },
+    });
+    // An empty {} needs room for the member
+    if let &mut Node::Object {
+        ref mut members,
+        ref mut close,
+        ..
+    } = object
+    {
+        if members.len() == 1 && close.leading.is_empty() {
+            close.leading.push(Trivia::Whitespace(" ".to_string()));
+        }
+    }
+    Ok(())
+}
+
+fn push_element(array: &mut Node, value: &Value) -> Result<(), String> {
+    let (elements, close) = match array {
+        &mut Node::Array {
+            ref mut elements,
+            ref mut close,
+            ..
+        } => (elements, close),
+        _ => return Err("Expected a list".to_string()),
+    };
+    let mut node = Node::from_value(value);
+    let trailing_comma = match elements.last_mut() {
+        Some(last) => {
+            node.first_token_mut().leading = spacing(last.value.first_token());
+            let trailing = last.comma.is_some();
+            if !trailing {
+                last.comma = comma();
+            }
+            trailing
+        }
+        None => false,
+    };
+    elements.push(Element {
+        value: node,
+        comma: if trailing_comma { comma() } else { None },
+    });
+    // A list that had one element may have been written tightly as ['a']
+    if elements.len() == 2 && elements[0].value.first_token().leading.is_empty() {
+        elements[0].value.first_token_mut().leading = vec![Trivia::Whitespace(" ".to_string())];
+        elements[1].value.first_token_mut().leading = vec![Trivia::Whitespace(" ".to_string())];
+        close.leading = vec![Trivia::Whitespace(" ".to_string())];
+    }
+    Ok(())
+}
+
+// Member names are compared without the '*' that makes them optional
+fn member_position(object: &Node, name: &str) -> Option<usize> {
+    match object {
+        Node::Object { members, .. } => members
+            .iter()
+            .position(|m| m.key.unquoted().trim_start_matches('*') == name),
+        _ => None,
+    }
+}
+
+// Features and enum values are either plain strings or objects with a
+// 'name'
+fn value_name(value: &Node) -> Option<String> {
+    match value {
+        Node::Scalar(t) => Some(t.unquoted()),
+        _ => match value.get("name") {
+            Some(Node::Scalar(t)) => Some(t.unquoted()),
+            _ => None,
+        },
+    }
+}
+
+fn strings(list: &Node) -> Vec<String> {
+    match list {
+        Node::Array { elements, .. } => elements
+            .iter()
+            .filter_map(|e| value_name(&e.value))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+// Add a feature to the 'features' list of an object, creating the list
+// when needed
+fn push_feature(object: &mut Node, feature: &str) -> Result<(), String> {
+    match object.get_mut("features") {
+        Some(features) => {
+            if strings(features).iter().any(|f| f == feature) {
+                return Err(format!("Feature {} is already there", feature));
+            }
+            push_element(features, &Value::String(feature.to_string()))
+        }
+        None => push_member(
+            object,
+            "features",
+            &Value::Array(vec![Value::String(feature.to_string())]),
+        ),
+    }
+}
+
+// Type references in a member value: 'Type', ['Type'] or the long form
+fn rename_type(node: &mut Node, old: &str, new: &str) {
+    match *node {
+        Node::Scalar(ref mut t) => {
+            if t.kind == TokenKind::String && t.unquoted() == old {
+                set_string(t, new);
+            }
+        }
+        Node::Array {
+            ref mut elements, ..
+        } => {
+            for e in elements {
+                rename_type(&mut e.value, old, new);
+            }
+        }
+        Node::Object { .. } => {
+            if let Some(t) = node.get_mut("type") {
+                rename_type(t, old, new);
+            }
+        }
+    }
+}
+
+// 'data' and 'base' are either a type name or a dict of members
+fn rename_members(node: &mut Node, old: &str, new: &str) {
+    match node {
+        &mut Node::Object {
+            ref mut members, ..
+        } => {
+            for m in members {
+                rename_type(&mut m.value, old, new);
+            }
+        }
+        _ => rename_type(node, old, new),
+    }
+}
+
+// Everything before the doc comment, and the doc comment itself
+fn split_doc(leading: &[Trivia]) -> (Vec<Trivia>, Vec<Trivia>) {
+    let mut start = leading.len();
+    let mut i = leading.len();
+    while i > 0 {
+        i -= 1;
+        match leading[i] {
+            Trivia::Comment(_) => start = i,
+            Trivia::Whitespace(ref w) if w.matches('\n').count() > 1 && start < leading.len() => {
+                break
+            }
+            _ => {}
+        }
+    }
+    (leading[..start].to_vec(), leading[start..].to_vec())
+}
+
+fn separated(doc: Vec<Trivia>) -> Vec<Trivia> {
+    let mut leading = vec![Trivia::Whitespace("\n\n".to_string())];
+    leading.extend(doc);
+    leading
+}
+
+impl SyntaxTree {
+    /// The index of the definition with the given name
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.definitions
+            .iter()
+            .position(|d| definition_name(d).is_some_and(|(_, n)| n == name))
+    }
+
+    fn definition_mut(&mut self, name: &str) -> Result<(&'static str, &mut Node), String> {
+        match self.position(name) {
+            Some(i) => {
+                let kind = definition_name(&self.definitions[i]).unwrap().0;
+                Ok((kind, &mut self.definitions[i]))
+            }
+            None => Err(format!("{} is not defined in the schema", name)),
+        }
+    }
+
+    /// Add a member to a struct, command or event, a branch to an
+    /// alternate, or a member to the inline base of a union.  Start the
+    /// name with '*' to make it optional.
+    pub fn add_member(
+        &mut self,
+        definition: &str,
+        name: &str,
+        member_type: &Value,
+    ) -> Result<(), String> {
+        let (kind, node) = self.definition_mut(definition)?;
+        let key = match kind {
+            "union" => "base",
+            "enum" => return Err(format!("{} is an enum, add a value instead", definition)),
+            _ => "data",
+        };
+        match node.get_mut(key) {
+            Some(members @ &mut Node::Object { .. }) => {
+                if member_position(members, name.trim_start_matches('*')).is_some() {
+                    return Err(format!("{} already has a member {}", definition, name));
+                }
+                push_member(members, name, member_type)
+            }
+            Some(_) => Err(format!(
+                "The {} of {} is a named type, change that instead",
+                key, definition
+            )),
+            None => {
+                let mut members = serde_json::Map::new();
+                members.insert(name.to_string(), member_type.clone());
+                push_member(node, key, &Value::Object(members))
+            }
+        }
+    }
+
+    pub fn add_enum_value(&mut self, definition: &str, value: &str) -> Result<(), String> {
+        let (kind, node) = self.definition_mut(definition)?;
+        if kind != "enum" {
+            return Err(format!("{} is not an enum", definition));
+        }
+        match node.get_mut("data") {
+            Some(values) => {
+                if strings(values).iter().any(|v| v == value) {
+                    return Err(format!("{} already has a value {}", definition, value));
+                }
+                push_element(values, &Value::String(value.to_string()))
+            }
+            None => push_member(
+                node,
+                "data",
+                &Value::Array(vec![Value::String(value.to_string())]),
+            ),
+        }
+    }
+
+    /// Add a feature to a definition, or to one of its members or enum
+    /// values.  Members written in the short form are turned into the
+    /// { 'type': ..., 'features': [...] } form.
+    pub fn add_feature(
+        &mut self,
+        definition: &str,
+        member: Option<&str>,
+        feature: &str,
+    ) -> Result<(), String> {
+        let (kind, node) = self.definition_mut(definition)?;
+        let member = match member {
+            Some(m) => m,
+            None => return push_feature(node, feature),
+        };
+        let missing = || format!("{} has no member {}", definition, member);
+        if kind == "enum" {
+            let elements = match node.get_mut("data") {
+                Some(&mut Node::Array {
+                    ref mut elements, ..
+                }) => elements,
+                _ => return Err(missing()),
+            };
+            let element = elements
+                .iter_mut()
+                .find(|e| value_name(&e.value).is_some_and(|v| v == member))
+                .ok_or_else(missing)?;
+            return long_form(&mut element.value, "name", feature);
+        }
+        let key = if kind == "union" { "base" } else { "data" };
+        let members = match node.get_mut(key) {
+            Some(&mut Node::Object {
+                ref mut members, ..
+            }) => members,
+            _ => return Err(missing()),
+        };
+        let m = members
+            .iter_mut()
+            .find(|m| m.key.unquoted().trim_start_matches('*') == member)
+            .ok_or_else(missing)?;
+        long_form(&mut m.value, "type", feature)
+    }
+
+    /// Rename a definition along with every reference to it and the name
+    /// line of its doc comment
+    pub fn rename(&mut self, old: &str, new: &str) -> Result<(), String> {
+        if self.position(new).is_some() {
+            return Err(format!("{} is already defined in the schema", new));
+        }
+        let (kind, node) = self.definition_mut(old)?;
+        if let Some(&mut Node::Scalar(ref mut t)) = node.get_mut(kind) {
+            set_string(t, new);
+        }
+        let doc_name = format!("# @{}:", old);
+        for trivia in node.first_token_mut().leading.iter_mut() {
+            if let &mut Trivia::Comment(ref mut c) = trivia {
+                if c.trim_end() == doc_name {
+                    *c = format!("# @{}:", new);
+                }
+            }
+        }
+
+        for definition in &mut self.definitions {
+            // The data of an enum are its values, which aren't types
+            let is_enum = definition_name(definition).is_some_and(|(kind, _)| kind == "enum");
+            for key in &["data", "base", "returns"] {
+                if *key == "data" && is_enum {
+                    continue;
+                }
+                if let Some(node) = definition.get_mut(key) {
+                    rename_members(node, old, new);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Move a definition, along with its doc comment, in front of another
+    /// one, or to the end with `None`.  Comments at the top of the file
+    /// stay there.
+    pub fn move_definition(&mut self, name: &str, before: Option<&str>) -> Result<(), String> {
+        let from = self
+            .position(name)
+            .ok_or_else(|| format!("{} is not defined in the schema", name))?;
+        let mut node = self.definitions.remove(from);
+        if from == 0 && !self.definitions.is_empty() {
+            let (header, doc) = split_doc(&node.first_token().leading);
+            let (_, first_doc) = split_doc(&self.definitions[0].first_token().leading);
+            let mut leading = header;
+            leading.extend(first_doc);
+            self.definitions[0].first_token_mut().leading = leading;
+            node.first_token_mut().leading = separated(doc);
+        }
+
+        let to = match before {
+            Some(before) => match self.position(before) {
+                Some(i) => i,
+                None => {
+                    self.definitions.insert(from, node);
+                    return Err(format!("{} is not defined in the schema", before));
+                }
+            },
+            None => self.definitions.len(),
+        };
+        if to == 0 && !self.definitions.is_empty() {
+            let (header, doc) = split_doc(&self.definitions[0].first_token().leading);
+            let (_, moved_doc) = split_doc(&node.first_token().leading);
+            let mut leading = header;
+            leading.extend(moved_doc);
+            node.first_token_mut().leading = leading;
+            self.definitions[0].first_token_mut().leading = separated(doc);
+        }
+        self.definitions.insert(to, node);
+        Ok(())
+    }
+}
+
+// Turn a short form member or enum value into the long form if needed and
+// add the feature to it
+fn long_form(value: &mut Node, key: &str, feature: &str) -> Result<(), String> {
+    if let &mut Node::Object { .. } = value {
+        return push_feature(value, feature);
+    }
+    let leading = value.first_token().leading.clone();
+    let mut first = value.clone();
+    first.first_token_mut().leading = vec![Trivia::Whitespace(" ".to_string())];
+    let mut long = Node::from_value(&Value::Object(serde_json::Map::new()));
+    if let &mut Node::Object {
+        ref mut members,
+        ref mut close,
+        ..
+    } = &mut long
+    {
+        members.push(Member {
+            key: {
+                let mut k = Token::string(key);
+                k.leading = vec![Trivia::Whitespace(" ".to_string())];
+                k
+            },
+            colon: Token::new(TokenKind::Colon, ":"),
+            value: first,
+            comma: None,
+        });
+        close.leading = vec![Trivia::Whitespace(" ".to_string())];
+    }
+    push_member(
+        &mut long,
+        "features",
+        &Value::Array(vec![Value::String(feature.to_string())]),
+    )?;
+    long.first_token_mut().leading = leading;
+    *value = long;
+    Ok(())
+}
+
+#[test]
+fn test_edit() {
+    let input = r#"# -*- Mode: Python -*-
+
+##
+# @RunState:
+##
+{ 'enum': 'RunState', 'data': [ 'running', 'paused' ] }
+
+##
+# @StatusInfo:
+##
+{ 'struct': 'StatusInfo',
+  'data': {'running': 'bool',  # whether the guest runs
+           'status': 'RunState'} }
+
+{ 'command': 'query-status', 'returns': 'StatusInfo' }
+"#;
+    let mut tree = SyntaxTree::parse(input).unwrap();
+    tree.add_member("StatusInfo", "*reason", &Value::String("str".to_string()))
+        .unwrap();
+    tree.add_enum_value("RunState", "debug").unwrap();
+    tree.add_feature("RunState", Some("paused"), "deprecated")
+        .unwrap();
+    tree.add_feature("query-status", None, "unstable").unwrap();
+    tree.add_feature("StatusInfo", Some("status"), "unstable")
+        .unwrap();
+    assert_eq!(
+        tree.to_string(),
+        r#"# -*- Mode: Python -*-
+
+##
+# @RunState:
+##
+{ 'enum': 'RunState', 'data': [ 'running', { 'name': 'paused', 'features': ['deprecated'] }, 'debug' ] }
+
+##
+# @StatusInfo:
+##
+{ 'struct': 'StatusInfo',
+  'data': {'running': 'bool',  # whether the guest runs
+           'status': { 'type': 'RunState', 'features': ['unstable'] },
+           '*reason': 'str'} }
+
+{ 'command': 'query-status', 'returns': 'StatusInfo', 'features': ['unstable'] }
+"#
+    );
+    assert!(tree
+        .add_member("StatusInfo", "reason", &Value::String("int".to_string()))
+        .is_err());
+    assert!(tree.add_enum_value("StatusInfo", "x").is_err());
+
+    let mut tree = SyntaxTree::parse(input).unwrap();
+    tree.rename("StatusInfo", "VmStatus").unwrap();
+    tree.move_definition("RunState", None).unwrap();
+    assert_eq!(
+        tree.to_string(),
+        r#"# -*- Mode: Python -*-
+
+##
+# @VmStatus:
+##
+{ 'struct': 'VmStatus',
+  'data': {'running': 'bool',  # whether the guest runs
+           'status': 'RunState'} }
+
+{ 'command': 'query-status', 'returns': 'VmStatus' }
+
+##
+# @RunState:
+##
+{ 'enum': 'RunState', 'data': [ 'running', 'paused' ] }
+"#
+    );
+    tree.move_definition("RunState", Some("VmStatus")).unwrap();
+    assert_eq!(tree.to_string(), input.replace("StatusInfo", "VmStatus"));
+    assert!(tree.rename("VmStatus", "RunState").is_err());
+
+    let mut tree = SyntaxTree::parse(
+        "{ 'enum': 'Kind', 'data': [ 'Info', 'other' ] }\n\
+         { 'struct': 'Info', 'data': { 'kind': 'Kind' } }\n\
+         { 'command': 'query-info', 'returns': ['Info'] }\n",
+    )
+    .unwrap();
+    tree.rename("Info", "KindInfo").unwrap();
+    assert_eq!(
+        tree.to_string(),
+        "{ 'enum': 'Kind', 'data': [ 'Info', 'other' ] }\n\
+         { 'struct': 'KindInfo', 'data': { 'kind': 'Kind' } }\n\
+         { 'command': 'query-info', 'returns': ['KindInfo'] }\n"
+    );
+}
@@ -0,0 +1,375 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use cst::{Node, SyntaxTree, Token, TokenKind, Trivia};
+
+// Lines are wrapped to stay within this many columns where possible
+const WIDTH: usize = 80;
+
+// Keeps track of the column so nested values can line up
+struct Writer {
+    out: String,
+    column: usize,
+}
+
+impl Writer {
+    fn write(&mut self, text: &str) {
+        self.out.push_str(text);
+        self.column = match text.rfind('\n') {
+            Some(i) => text[i + 1..].chars().count(),
+            None => self.column + text.chars().count(),
+        };
+    }
+
+    fn newline(&mut self, indent: usize) {
+        let trimmed = self.out.trim_end_matches(' ').len();
+        self.out.truncate(trimmed);
+        self.out.push('\n');
+        self.out.push_str(&" ".repeat(indent));
+        self.column = indent;
+    }
+
+    // A comment that shares the line with code always ends that line
+    fn trailing_comment(&mut self, comment: &str, indent: usize) {
+        self.write("  ");
+        self.write(comment.trim_end());
+        self.newline(indent);
+    }
+
+    fn comment_line(&mut self, comment: &str, indent: usize) {
+        self.write(comment.trim_end());
+        self.newline(indent);
+    }
+}
+
+// A comment inside a definition, and whether it followed code on the same
+// line
+struct Comment {
+    text: String,
+    trailing: bool,
+}
+
+fn comments(trivia: &[Trivia]) -> Vec<Comment> {
+    let mut comments = Vec::new();
+    let mut trailing = true;
+    for t in trivia {
+        match t {
+            Trivia::Comment(c) => {
+                comments.push(Comment {
+                    text: c.clone(),
+                    trailing,
+                });
+                trailing = false;
+            }
+            Trivia::Whitespace(w) => trailing = trailing && !w.contains('\n'),
+        }
+    }
+    comments
+}
+
+fn commented(t: &Token) -> bool {
+    t.leading.iter().any(|t| matches!(t, &Trivia::Comment(_)))
+}
+
+fn has_comments(node: &Node, with_leading: bool) -> bool {
+    match node {
+        Node::Scalar(t) => with_leading && commented(t),
+        Node::Object {
+            open,
+            members,
+            close,
+        } => {
+            (with_leading && commented(open))
+                || commented(close)
+                || members.iter().any(|m| {
+                    commented(&m.key)
+                        || commented(&m.colon)
+                        || has_comments(&m.value, true)
+                        || m.comma.as_ref().is_some_and(commented)
+                })
+        }
+        Node::Array {
+            open,
+            elements,
+            close,
+        } => {
+            (with_leading && commented(open))
+                || commented(close)
+                || elements.iter().any(|e| {
+                    has_comments(&e.value, true) || e.comma.as_ref().is_some_and(commented)
+                })
+        }
+    }
+}
+
+// Strings always get single quotes
+fn scalar(token: &Token) -> String {
+    match token.kind {
+        TokenKind::String => Token::string(&token.unquoted()).text,
+        _ => token.text.clone(),
+    }
+}
+
+// The node on a single line, if it has no comments in the way
+fn flat(node: &Node) -> Option<String> {
+    if has_comments(node, false) {
+        return None;
+    }
+    Some(match node {
+        Node::Scalar(t) => scalar(t),
+        Node::Object { members, .. } if members.is_empty() => "{}".to_string(),
+        Node::Object { members, .. } => {
+            let members: Option<Vec<String>> = members
+                .iter()
+                .map(|m| flat(&m.value).map(|v| format!("{}: {}", scalar(&m.key), v)))
+                .collect();
+            format!("{{ {} }}", members?.join(", "))
+        }
+        Node::Array { elements, .. } if elements.is_empty() => "[]".to_string(),
+        // Array types like ['int'] are written without the spaces
+        Node::Array { elements, .. }
+            if elements.len() == 1 && matches!(elements[0].value, Node::Scalar(_)) =>
+        {
+            format!("[{}]", flat(&elements[0].value)?)
+        }
+        Node::Array { elements, .. } => {
+            let elements: Option<Vec<String>> = elements.iter().map(|e| flat(&e.value)).collect();
+            format!("[ {} ]", elements?.join(", "))
+        }
+    })
+}
+
+// Write the comments in front of an item.  Comments that followed code in
+// the source stay at the end of that line, as does the first comment after
+// an opening bracket.  Returns whether the item now starts on a new line.
+fn item_comments(w: &mut Writer, comments: Vec<Comment>, first: bool, indent: usize) -> bool {
+    if comments.is_empty() {
+        return false;
+    }
+    for (i, comment) in comments.iter().enumerate() {
+        if i == 0 && (comment.trailing || first) {
+            w.trailing_comment(&comment.text, indent);
+        } else {
+            if i == 0 {
+                w.newline(indent);
+            }
+            w.comment_line(&comment.text, indent);
+        }
+    }
+    true
+}
+
+fn close(w: &mut Writer, comments: Vec<Comment>, indent: usize, bracket: &str) {
+    if comments.is_empty() {
+        w.write(" ");
+        w.write(bracket);
+        return;
+    }
+    for (i, comment) in comments.iter().enumerate() {
+        if i == 0 && comment.trailing {
+            w.trailing_comment(&comment.text, indent);
+        } else {
+            if i == 0 {
+                w.newline(indent);
+            }
+            w.comment_line(&comment.text, indent);
+        }
+    }
+    let column = indent - 2;
+    w.newline(column);
+    w.write(bracket);
+}
+
+fn node(w: &mut Writer, node: &Node) {
+    if let Some(flat) = flat(node) {
+        if w.column + flat.chars().count() <= WIDTH {
+            w.write(&flat);
+            return;
+        }
+    }
+    let indent = w.column + 2;
+    match node {
+        Node::Scalar(t) => w.write(&scalar(t)),
+        // Members go one per line, lined up under the first
+        Node::Object {
+            members,
+            close: end,
+            ..
+        } => {
+            w.write("{ ");
+            let mut pending: Vec<Comment> = Vec::new();
+            for (i, m) in members.iter().enumerate() {
+                if i > 0 {
+                    w.write(",");
+                }
+                pending.extend(comments(&m.key.leading));
+                pending.extend(comments(&m.colon.leading));
+                pending.extend(comments(&m.value.first_token().leading));
+                let commented = item_comments(w, pending, i == 0, indent);
+                if i > 0 && !commented {
+                    w.newline(indent);
+                }
+                w.write(&scalar(&m.key));
+                w.write(": ");
+                self::node(w, &m.value);
+                pending = m
+                    .comma
+                    .as_ref()
+                    .map(|c| comments(&c.leading))
+                    .unwrap_or_default();
+            }
+            pending.extend(comments(&end.leading));
+            close(w, pending, indent, "}");
+        }
+        // Elements fill up the line before wrapping
+        Node::Array {
+            elements,
+            close: end,
+            ..
+        } => {
+            w.write("[ ");
+            let mut pending: Vec<Comment> = Vec::new();
+            for (i, e) in elements.iter().enumerate() {
+                if i > 0 {
+                    w.write(",");
+                }
+                pending.extend(comments(&e.value.first_token().leading));
+                let commented = item_comments(w, pending, i == 0, indent);
+                if i > 0 && !commented {
+                    let fits = flat(&e.value)
+                        .is_some_and(|f| w.column + 1 + f.chars().count() + 2 <= WIDTH);
+                    if fits {
+                        w.write(" ");
+                    } else {
+                        w.newline(indent);
+                    }
+                }
+                self::node(w, &e.value);
+                pending = e
+                    .comma
+                    .as_ref()
+                    .map(|c| comments(&c.leading))
+                    .unwrap_or_default();
+            }
+            pending.extend(comments(&end.leading));
+            close(w, pending, indent, "]");
+        }
+    }
+}
+
+// Blocks of comment lines, split where the source has blank lines
+fn comment_blocks(trivia: &[Trivia]) -> Vec<Vec<String>> {
+    let mut blocks: Vec<Vec<String>> = vec![Vec::new()];
+    for t in trivia {
+        match t {
+            Trivia::Comment(c) => blocks.last_mut().unwrap().push(c.trim_end().to_string()),
+            Trivia::Whitespace(w) => {
+                if w.matches('\n').count() > 1 && !blocks.last().unwrap().is_empty() {
+                    blocks.push(Vec::new());
+                }
+            }
+        }
+    }
+    blocks.retain(|b| !b.is_empty());
+    blocks
+}
+
+impl SyntaxTree {
+    /// The schema in QEMU's canonical style: single quotes, a blank line
+    /// between definitions with their doc comment right above them, objects
+    /// that don't fit on a line with one member per line lined up under the
+    /// first, and lists wrapped at 80 columns.  Comments are kept.
+    pub fn format(&self) -> String {
+        let mut w = Writer {
+            out: String::new(),
+            column: 0,
+        };
+        for (i, definition) in self.definitions.iter().enumerate() {
+            if i > 0 {
+                w.write("\n");
+            }
+            // The last block of comments is the doc comment and goes right
+            // above the definition
+            for (j, block) in comment_blocks(&definition.first_token().leading)
+                .into_iter()
+                .enumerate()
+            {
+                if j > 0 {
+                    w.write("\n");
+                }
+                for line in block {
+                    w.write(&line);
+                    w.write("\n");
+                }
+            }
+            node(&mut w, definition);
+            w.write("\n");
+        }
+        for block in comment_blocks(&self.trailing) {
+            w.write("\n");
+            for line in block {
+                w.write(&line);
+                w.write("\n");
+            }
+        }
+        w.out
+    }
+}
+
+/// Format QAPI source in the canonical style
+pub fn format_source(input: &str) -> Result<String, String> {
+    Ok(SyntaxTree::parse(input)?.format())
+}
+
+/// The files that would change when formatted
+pub fn check_format<P: AsRef<Path>>(paths: &[P]) -> Result<Vec<PathBuf>, String> {
+    let mut unformatted = Vec::new();
+    for path in paths {
+        let path = path.as_ref();
+        let mut input = String::new();
+        File::open(path)
+            .and_then(|mut f| f.read_to_string(&mut input))
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        let formatted = format_source(&input).map_err(|e| format!("{}: {}", path.display(), e))?;
+        if formatted != input {
+            unformatted.push(path.to_path_buf());
+        }
+    }
+    Ok(unformatted)
+}
+
+#[test]
+fn test_format() {
+    let input = r#"# -*- Mode: Python -*-
+##
+# @StatusInfo:
+##
+
+{"struct":"StatusInfo",
+    'data': {'running': 'bool',  # whether the guest runs
+           "singlestep": 'bool', 'status': 'RunState', 'very-long-member-name': ['str'] } }
+{ 'enum': 'RunState', 'data': [ 'debug', 'inmigrate', 'internal-error', 'io-error', 'paused',
+ 'postmigrate', 'prelaunch' ] }
+{ 'command': 'query-status',     'returns': 'StatusInfo' }
+"#;
+    let expected = r#"# -*- Mode: Python -*-
+##
+# @StatusInfo:
+##
+{ 'struct': 'StatusInfo',
+  'data': { 'running': 'bool',  # whether the guest runs
+            'singlestep': 'bool',
+            'status': 'RunState',
+            'very-long-member-name': ['str'] } }
+
+{ 'enum': 'RunState',
+  'data': [ 'debug', 'inmigrate', 'internal-error', 'io-error', 'paused',
+            'postmigrate', 'prelaunch' ] }
+
+{ 'command': 'query-status', 'returns': 'StatusInfo' }
+"#;
+    let formatted = format_source(input).unwrap();
+    assert_eq!(formatted, expected);
+    assert_eq!(format_source(&formatted).unwrap(), formatted);
+}
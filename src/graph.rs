@@ -0,0 +1,438 @@
+extern crate serde_json;
+
+use std::cmp;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+use self::serde_json::Value;
+
+use schema::{is_builtin, member_type, Schema};
+use QemuType;
+
+/// Where in a definition a reference to another type was found
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DependencyKind {
+    Base,
+    Member,
+    Branch,
+    Arguments,
+    Returns,
+    Data,
+}
+
+/// A reference from one definition to a named, non builtin type
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Dependency {
+    pub name: String,
+    pub kind: DependencyKind,
+    /// The type is used as the element of an array, so it's already behind
+    /// a pointer in the generated code
+    pub array: bool,
+}
+
+/// The type dependency graph of a schema.  Every named definition is a node
+/// and every reference to another type is an edge.
+#[derive(Debug)]
+pub struct DependencyGraph {
+    nodes: Vec<String>,
+    edges: HashMap<String, Vec<Dependency>>,
+    // Strongly connected component of every node, only following edges
+    // that embed the type by value
+    by_value: HashMap<String, usize>,
+    by_value_cycles: BTreeSet<usize>,
+}
+
+fn references(value: &Value, kind: DependencyKind, array: bool, deps: &mut Vec<Dependency>) {
+    match value {
+        Value::String(s) if !is_builtin(s) => {
+            deps.push(Dependency {
+                name: s.clone(),
+                kind,
+                array,
+            });
+        }
+        Value::Array(a) => {
+            for element in a {
+                references(element, kind, true, deps);
+            }
+        }
+        // Anonymous structs, like inline command arguments
+        Value::Object(o) => {
+            for member in o.values() {
+                references(member_type(member), kind, array, deps);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn dependencies(qemu_type: &QemuType) -> Vec<Dependency> {
+    let mut deps: Vec<Dependency> = Vec::new();
+    match qemu_type {
+        QemuType::Struct(s) => {
+            references(&s.base, DependencyKind::Base, false, &mut deps);
+            references(&s.fields, DependencyKind::Member, false, &mut deps);
+        }
+        QemuType::Union(u) => {
+            references(&u.base, DependencyKind::Base, false, &mut deps);
+            references(&u.data, DependencyKind::Branch, false, &mut deps);
+        }
+        QemuType::Alternate(a) => {
+            references(&a.data, DependencyKind::Branch, false, &mut deps);
+        }
+        QemuType::Command(c) => {
+            references(&c.fields, DependencyKind::Arguments, false, &mut deps);
+            references(&c.returns, DependencyKind::Returns, false, &mut deps);
+        }
+        QemuType::Event(e) => {
+            references(&e.data, DependencyKind::Data, false, &mut deps);
+        }
+        _ => {}
+    }
+    deps
+}
+
+// Tarjan's algorithm.  Components come out with everything they depend on
+// before them.
+struct Tarjan<'a> {
+    edges: &'a HashMap<String, Vec<Dependency>>,
+    by_value_only: bool,
+    index: HashMap<&'a str, usize>,
+    low: HashMap<&'a str, usize>,
+    stack: Vec<&'a str>,
+    components: Vec<Vec<String>>,
+}
+
+impl<'a> Tarjan<'a> {
+    fn visit(&mut self, node: &'a str) {
+        let index = self.index.len();
+        self.index.insert(node, index);
+        self.low.insert(node, index);
+        self.stack.push(node);
+
+        let edges = self.edges;
+        for dep in &edges[node] {
+            if self.by_value_only && dep.array {
+                continue;
+            }
+            let next = dep.name.as_str();
+            if !self.edges.contains_key(next) {
+                // Not defined in this schema
+                continue;
+            }
+            if !self.index.contains_key(next) {
+                self.visit(next);
+                let low = cmp::min(self.low[node], self.low[next]);
+                self.low.insert(node, low);
+            } else if self.stack.contains(&next) {
+                let low = cmp::min(self.low[node], self.index[next]);
+                self.low.insert(node, low);
+            }
+        }
+
+        if self.low[node] == self.index[node] {
+            let mut component: Vec<String> = Vec::new();
+            loop {
+                let member = self.stack.pop().unwrap();
+                component.push(member.to_string());
+                if member == node {
+                    break;
+                }
+            }
+            component.reverse();
+            self.components.push(component);
+        }
+    }
+}
+
+impl DependencyGraph {
+    pub fn new(schema: &Schema) -> DependencyGraph {
+        let mut nodes: Vec<String> = Vec::new();
+        let mut edges: HashMap<String, Vec<Dependency>> = HashMap::new();
+        for definition in schema.definitions() {
+            let name = definition.name().unwrap().to_string();
+            edges.insert(name.clone(), dependencies(definition));
+            nodes.push(name);
+        }
+        let mut graph = DependencyGraph {
+            nodes,
+            edges,
+            by_value: HashMap::new(),
+            by_value_cycles: BTreeSet::new(),
+        };
+
+        for (i, component) in graph.components(true).into_iter().enumerate() {
+            if graph.is_cycle(&component, true) {
+                graph.by_value_cycles.insert(i);
+            }
+            for name in component {
+                graph.by_value.insert(name, i);
+            }
+        }
+        graph
+    }
+
+    /// Every definition in the graph, in schema order
+    pub fn definitions(&self) -> &[String] {
+        &self.nodes
+    }
+
+    /// The types a definition refers to directly
+    pub fn dependencies(&self, name: &str) -> &[Dependency] {
+        match self.edges.get(name) {
+            Some(deps) => deps,
+            None => &[],
+        }
+    }
+
+    /// References to types that aren't defined in this schema
+    pub fn unresolved(&self) -> Vec<(&str, &Dependency)> {
+        let mut unresolved = Vec::new();
+        for name in &self.nodes {
+            for dep in &self.edges[name] {
+                if !self.edges.contains_key(&dep.name) {
+                    unresolved.push((name.as_str(), dep));
+                }
+            }
+        }
+        unresolved
+    }
+
+    /// The definitions that refer to a type directly, in schema order
+    pub fn users(&self, name: &str) -> Vec<&str> {
+        self.nodes
+            .iter()
+            .filter(|n| self.edges[*n].iter().any(|d| d.name == name))
+            .map(|n| n.as_str())
+            .collect()
+    }
+
+    /// Every type a definition refers to, directly or through other types
+    pub fn reachable_from(&self, name: &str) -> BTreeSet<String> {
+        let mut seen: BTreeSet<String> = BTreeSet::new();
+        let mut queue: VecDeque<&str> = VecDeque::new();
+        queue.push_back(name);
+        while let Some(next) = queue.pop_front() {
+            for dep in self.dependencies(next) {
+                if seen.insert(dep.name.clone()) {
+                    queue.push_back(&dep.name);
+                }
+            }
+        }
+        seen
+    }
+
+    /// Every definition that refers to a type, directly or through other
+    /// types
+    pub fn users_of(&self, name: &str) -> BTreeSet<String> {
+        let mut seen: BTreeSet<String> = BTreeSet::new();
+        let mut queue: VecDeque<&str> = VecDeque::new();
+        queue.push_back(name);
+        while let Some(next) = queue.pop_front() {
+            for user in self.users(next) {
+                if seen.insert(user.to_string()) {
+                    queue.push_back(user);
+                }
+            }
+        }
+        seen
+    }
+
+    /// The definition refers to the type through one of its `kind`
+    /// references, directly or through other types
+    pub fn refers_to(&self, from: &str, kind: DependencyKind, to: &str) -> bool {
+        self.dependencies(from)
+            .iter()
+            .filter(|d| d.kind == kind)
+            .any(|d| d.name == to || self.reachable_from(&d.name).contains(to))
+    }
+
+    // Every type reachable through a reference of one of the given kinds
+    fn used_through(&self, kinds: &[DependencyKind]) -> BTreeSet<String> {
+        let mut types: BTreeSet<String> = BTreeSet::new();
+        for name in &self.nodes {
+            for dep in &self.edges[name] {
+                if kinds.contains(&dep.kind) {
+                    types.insert(dep.name.clone());
+                    types.extend(self.reachable_from(&dep.name));
+                }
+            }
+        }
+        types
+    }
+
+    /// Types a client can send to QEMU as part of command arguments
+    pub fn input_types(&self) -> BTreeSet<String> {
+        self.used_through(&[DependencyKind::Arguments])
+    }
+
+    /// Types a client can receive from QEMU in command returns or events
+    pub fn output_types(&self) -> BTreeSet<String> {
+        self.used_through(&[DependencyKind::Returns, DependencyKind::Data])
+    }
+
+    fn components(&self, by_value_only: bool) -> Vec<Vec<String>> {
+        let mut tarjan = Tarjan {
+            edges: &self.edges,
+            by_value_only,
+            index: HashMap::new(),
+            low: HashMap::new(),
+            stack: Vec::new(),
+            components: Vec::new(),
+        };
+        for name in &self.nodes {
+            if !tarjan.index.contains_key(name.as_str()) {
+                tarjan.visit(name);
+            }
+        }
+        tarjan.components
+    }
+
+    fn is_cycle(&self, component: &[String], by_value_only: bool) -> bool {
+        component.len() > 1
+            || self.edges[&component[0]]
+                .iter()
+                .any(|d| d.name == component[0] && !(by_value_only && d.array))
+    }
+
+    /// Groups of definitions that refer to each other, with every group
+    /// ordered after the groups it depends on.  Definitions that aren't part
+    /// of a cycle are in a group of their own.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<String>> {
+        self.components(false)
+    }
+
+    /// Every set of definitions that refer to each other, directly or
+    /// through other types
+    pub fn cycles(&self) -> Vec<Vec<String>> {
+        self.components(false)
+            .into_iter()
+            .filter(|c| self.is_cycle(c, false))
+            .collect()
+    }
+
+    /// Definitions ordered so that every type comes after the types it
+    /// depends on.  Fails with the cycles if there's no such order.
+    pub fn topological_order(&self) -> Result<Vec<String>, Vec<Vec<String>>> {
+        let cycles = self.cycles();
+        if !cycles.is_empty() {
+            return Err(cycles);
+        }
+        Ok(self
+            .components(false)
+            .into_iter()
+            .flat_map(|c| c.into_iter())
+            .collect())
+    }
+
+    /// The type contains itself by value, through any number of other types.
+    /// Going through an array doesn't count since a Vec is already a pointer.
+    pub fn is_recursive(&self, name: &str) -> bool {
+        match self.by_value.get(name) {
+            Some(c) => self.by_value_cycles.contains(c),
+            None => false,
+        }
+    }
+
+    /// Embedding `to` by value inside `from` would make an infinitely sized
+    /// Rust type, so it needs to go in a Box
+    pub fn needs_box(&self, from: &str, to: &str) -> bool {
+        match (self.by_value.get(from), self.by_value.get(to)) {
+            (Some(a), Some(b)) => a == b && self.by_value_cycles.contains(a),
+            _ => false,
+        }
+    }
+}
+
+#[test]
+fn test_recursive_through_alternate() {
+    let input = r#"
+{ 'struct': 'BlockdevOptionsBase', 'data': { 'driver': 'str' } }
+{ 'struct': 'BlockdevOptionsGenericFormat', 'data': { 'file': 'BlockdevRef' } }
+{ 'struct': 'BlockdevOptionsQuorum', 'data': { 'children': [ 'BlockdevRef' ] } }
+{ 'union': 'BlockdevOptions', 'base': 'BlockdevOptionsBase',
+  'discriminator': 'driver',
+  'data': { 'raw': 'BlockdevOptionsGenericFormat',
+            'quorum': 'BlockdevOptionsQuorum' } }
+{ 'alternate': 'BlockdevRef',
+  'data': { 'definition': 'BlockdevOptions', 'reference': 'str' } }
+{ 'command': 'blockdev-add', 'data': { 'options': 'BlockdevOptions' } }
+"#;
+    let schema = Schema::parse(input).unwrap();
+    let graph = DependencyGraph::new(&schema);
+
+    assert!(graph.is_recursive("BlockdevOptions"));
+    assert!(graph.is_recursive("BlockdevRef"));
+    assert!(!graph.is_recursive("BlockdevOptionsBase"));
+    // Only reachable through a Vec
+    assert!(!graph.is_recursive("BlockdevOptionsQuorum"));
+    assert!(graph.needs_box("BlockdevOptionsGenericFormat", "BlockdevRef"));
+    assert!(!graph.needs_box("blockdev-add", "BlockdevOptions"));
+
+    assert!(graph.topological_order().is_err());
+    let cycles = graph.cycles();
+    assert_eq!(cycles.len(), 1);
+    assert!(cycles[0].contains(&"BlockdevOptionsQuorum".to_string()));
+
+    let order: Vec<String> = graph
+        .strongly_connected_components()
+        .into_iter()
+        .flat_map(|c| c.into_iter())
+        .collect();
+    let position = |name: &str| order.iter().position(|n| n == name).unwrap();
+    assert!(position("BlockdevOptionsBase") < position("BlockdevOptions"));
+    assert!(position("BlockdevOptions") < position("blockdev-add"));
+}
+
+#[test]
+fn test_reverse_references() {
+    let input = r#"
+{ 'enum': 'Color', 'data': [ 'red', 'green' ] }
+{ 'struct': 'Layer', 'data': { 'color': 'Color' } }
+{ 'struct': 'Paint', 'data': { '*layers': ['Layer'] } }
+{ 'struct': 'Brush', 'data': { 'size': 'int' } }
+{ 'command': 'query-paint', 'returns': ['Paint'] }
+{ 'command': 'set-color', 'data': { 'color': 'Color' } }
+{ 'event': 'PAINT_DRY', 'data': { 'paint': 'Paint' } }
+"#;
+    let schema = Schema::parse(input).unwrap();
+    let graph = DependencyGraph::new(&schema);
+    assert_eq!(graph.users("Color"), vec!["Layer", "set-color"]);
+    assert_eq!(
+        graph.users_of("Color").into_iter().collect::<Vec<String>>(),
+        vec!["Layer", "PAINT_DRY", "Paint", "query-paint", "set-color"]
+    );
+    assert_eq!(
+        graph
+            .reachable_from("query-paint")
+            .into_iter()
+            .collect::<Vec<String>>(),
+        vec!["Color", "Layer", "Paint"]
+    );
+    assert!(graph.refers_to("query-paint", DependencyKind::Returns, "Color"));
+    assert!(!graph.refers_to("set-color", DependencyKind::Returns, "Color"));
+    assert!(graph.users_of("Brush").is_empty());
+}
+
+#[test]
+fn test_topological_order() {
+    let input = r#"
+{ 'enum': 'Color', 'data': [ 'red', 'green' ] }
+{ 'struct': 'Paint', 'data': { 'color': 'Color', '*layers': ['Layer'] } }
+{ 'struct': 'Layer', 'data': { 'color': 'Color' } }
+{ 'command': 'query-paint', 'returns': ['Paint'] }
+"#;
+    let schema = Schema::parse(input).unwrap();
+    let graph = DependencyGraph::new(&schema);
+    assert_eq!(
+        graph.topological_order().unwrap(),
+        vec!["Color", "Layer", "Paint", "query-paint"]
+    );
+    assert_eq!(
+        graph.dependencies("query-paint"),
+        &[Dependency {
+            name: "Paint".to_string(),
+            kind: DependencyKind::Returns,
+            array: true,
+        }]
+    );
+}
@@ -0,0 +1,863 @@
+extern crate serde_json;
+
+use std::collections::{HashMap, HashSet};
+
+use self::serde_json::{Map, Value};
+
+use schema::{is_builtin, member_type, names, Schema};
+use {QemuType, Union};
+
+// Implicit types get masked names like "123" or ":123" in QEMU's output
+// unless it was asked not to, in which case they keep their q_obj_ names
+fn is_implicit(name: &str) -> bool {
+    name.starts_with(':')
+        || name.starts_with("q_obj_")
+        || name == "q_empty"
+        || (!name.is_empty() && name.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn list<'a>(info: &'a Map<String, Value>, key: &str) -> &'a [Value] {
+    match info.get(key) {
+        Some(Value::Array(a)) => a,
+        _ => &[],
+    }
+}
+
+fn string<'a>(info: &'a Map<String, Value>, key: &str) -> Result<&'a str, String> {
+    match info.get(key) {
+        Some(Value::String(s)) => Ok(s),
+        _ => Err(format!("SchemaInfo without a {}: {:?}", key, info)),
+    }
+}
+
+// The SchemaInfo entries of a query-qmp-schema dump, indexed by name
+struct Introspection<'a> {
+    entities: Vec<&'a Map<String, Value>>,
+    types: HashMap<&'a str, &'a Map<String, Value>>,
+    // How often each type is used, and where: as "arg-type", "ret-type",
+    // "element-type", or in "members" or "variants"
+    uses: HashMap<(&'a str, &'static str), usize>,
+}
+
+impl<'a> Introspection<'a> {
+    fn new(input: &'a Value) -> Result<Introspection<'a>, String> {
+        // Accept the whole QMP reply as well as just the list
+        let input = input.get("return").unwrap_or(input);
+        let list = match input {
+            Value::Array(a) => a,
+            _ => return Err("Expected a list of SchemaInfo".to_string()),
+        };
+        let mut introspection = Introspection {
+            entities: Vec::new(),
+            types: HashMap::new(),
+            uses: HashMap::new(),
+        };
+        for entry in list {
+            let info = match entry {
+                Value::Object(o) => o,
+                _ => return Err(format!("Expected a SchemaInfo object: {:?}", entry)),
+            };
+            let name = string(info, "name")?;
+            introspection.types.insert(name, info);
+            introspection.entities.push(info);
+        }
+        for info in &introspection.entities {
+            for used in introspection.references(info) {
+                *introspection.uses.entry(used).or_insert(0) += 1;
+            }
+        }
+        Ok(introspection)
+    }
+
+    fn references(&self, info: &'a Map<String, Value>) -> Vec<(&'a str, &'static str)> {
+        let mut used = Vec::new();
+        for key in &["arg-type", "ret-type", "element-type"] {
+            if let Some(name) = info.get(*key).and_then(|v| v.as_str()) {
+                used.push((name, *key));
+            }
+        }
+        for key in &["members", "variants"] {
+            for member in list(info, key) {
+                if let Some(name) = member.get("type").and_then(|v| v.as_str()) {
+                    used.push((name, *key));
+                }
+            }
+        }
+        used
+    }
+
+    fn used(&self, name: &str, places: &[&'static str]) -> usize {
+        places
+            .iter()
+            .map(|place| self.uses.get(&(name, *place)).cloned().unwrap_or(0))
+            .sum()
+    }
+
+    fn lookup(&self, name: &str) -> Result<&'a Map<String, Value>, String> {
+        match self.types.get(name) {
+            Some(info) => Ok(info),
+            None => Err(format!("{} is not in the introspection data", name)),
+        }
+    }
+
+    fn meta_type(&self, name: &str) -> Result<&'a str, String> {
+        string(self.lookup(name)?, "meta-type")
+    }
+
+    fn is_empty_object(&self, name: &str) -> Result<bool, String> {
+        let info = self.lookup(name)?;
+        Ok(string(info, "meta-type")? == "object"
+            && list(info, "members").is_empty()
+            && list(info, "variants").is_empty())
+    }
+
+    // Arrays have their own SchemaInfo, the schema writes them as [T]
+    fn type_ref(&self, name: &str) -> Result<Value, String> {
+        if self.meta_type(name)? == "array" {
+            let element = string(self.lookup(name)?, "element-type")?;
+            return Ok(Value::Array(vec![self.type_ref(element)?]));
+        }
+        Ok(Value::String(name.to_string()))
+    }
+
+    // Command arguments and event data that only exist for that one command
+    // or event are written inline, like the schema does
+    fn is_inlined(&self, name: &str) -> Result<bool, String> {
+        let info = self.lookup(name)?;
+        Ok(is_implicit(name)
+            && string(info, "meta-type")? == "object"
+            && list(info, "variants").is_empty()
+            && self.used(name, &["arg-type"]) == 1
+            && self.used(name, &["ret-type", "element-type", "members", "variants"]) == 0)
+    }
+
+    // The empty object stands for missing arguments, return values and
+    // union branches, which the schema just leaves out
+    fn is_left_out(&self, name: &str) -> Result<bool, String> {
+        Ok(self.is_empty_object(name)? && self.used(name, &["element-type", "members"]) == 0)
+    }
+
+    fn arguments(&self, name: &str) -> Result<Value, String> {
+        if self.is_left_out(name)? {
+            return Ok(Value::Null);
+        }
+        if self.is_inlined(name)? {
+            return self.members(list(self.lookup(name)?, "members"));
+        }
+        self.type_ref(name)
+    }
+
+    fn returns(&self, name: &str) -> Result<Value, String> {
+        if self.is_left_out(name)? {
+            return Ok(Value::Null);
+        }
+        self.type_ref(name)
+    }
+
+    // Optional members are the ones with a "default", which is always null
+    fn members(&self, members: &[Value]) -> Result<Value, String> {
+        let mut result = Map::new();
+        for member in members {
+            let member = match member {
+                Value::Object(o) => o,
+                _ => return Err(format!("Expected a member object: {:?}", member)),
+            };
+            let name = string(member, "name")?;
+            let key = if member.contains_key("default") {
+                format!("*{}", name)
+            } else {
+                name.to_string()
+            };
+            let member_type = self.type_ref(string(member, "type")?)?;
+            let value = match member.get("features") {
+                Some(features) if !list(member, "features").is_empty() => {
+                    let mut long = Map::new();
+                    long.insert("type".to_string(), member_type);
+                    long.insert("features".to_string(), features.clone());
+                    Value::Object(long)
+                }
+                _ => member_type,
+            };
+            result.insert(key, value);
+        }
+        Ok(Value::Object(result))
+    }
+
+    fn definition(&self, info: &Map<String, Value>) -> Result<Option<Value>, String> {
+        let name = string(info, "name")?;
+        let mut definition = Map::new();
+        match string(info, "meta-type")? {
+            "builtin" | "array" => return Ok(None),
+            "object" => {
+                if self.is_inlined(name)? || self.is_left_out(name)? {
+                    return Ok(None);
+                }
+                let members = self.members(list(info, "members"))?;
+                match info.get("tag") {
+                    Some(tag) => {
+                        let mut branches = Map::new();
+                        for variant in list(info, "variants") {
+                            let variant = match variant {
+                                Value::Object(o) => o,
+                                _ => return Err(format!("Expected a variant: {:?}", variant)),
+                            };
+                            let branch = string(variant, "type")?;
+                            if !self.is_left_out(branch)? {
+                                branches.insert(
+                                    string(variant, "case")?.to_string(),
+                                    self.type_ref(branch)?,
+                                );
+                            }
+                        }
+                        definition.insert("union".to_string(), Value::String(name.to_string()));
+                        definition.insert("base".to_string(), members);
+                        definition.insert("discriminator".to_string(), tag.clone());
+                        definition.insert("data".to_string(), Value::Object(branches));
+                    }
+                    None => {
+                        definition.insert("struct".to_string(), Value::String(name.to_string()));
+                        definition.insert("data".to_string(), members);
+                    }
+                }
+            }
+            "enum" => {
+                // Newer QEMUs describe the values as members so they can
+                // carry features
+                let values: Vec<Value> = if info.contains_key("members") {
+                    list(info, "members")
+                        .iter()
+                        .map(|m| match m.get("features") {
+                            Some(Value::Array(f)) if !f.is_empty() => m.clone(),
+                            _ => m.get("name").cloned().unwrap_or(Value::Null),
+                        })
+                        .collect()
+                } else {
+                    list(info, "values").to_vec()
+                };
+                definition.insert("enum".to_string(), Value::String(name.to_string()));
+                definition.insert("data".to_string(), Value::Array(values));
+            }
+            "alternate" => {
+                // Alternate branches are anonymous on the wire, so they are
+                // named after their type
+                let mut branches = Map::new();
+                for member in list(info, "members") {
+                    let branch = match member.get("type").and_then(|v| v.as_str()) {
+                        Some(t) => t,
+                        None => {
+                            return Err(format!("Alternate member without a type: {:?}", member))
+                        }
+                    };
+                    branches.insert(branch.to_string(), self.type_ref(branch)?);
+                }
+                definition.insert("alternate".to_string(), Value::String(name.to_string()));
+                definition.insert("data".to_string(), Value::Object(branches));
+            }
+            "command" => {
+                definition.insert("command".to_string(), Value::String(name.to_string()));
+                let arguments = self.arguments(string(info, "arg-type")?)?;
+                if !arguments.is_null() {
+                    definition.insert("data".to_string(), arguments);
+                }
+                let returns = self.returns(string(info, "ret-type")?)?;
+                if !returns.is_null() {
+                    definition.insert("returns".to_string(), returns);
+                }
+                if let Some(oob) = info.get("allow-oob") {
+                    definition.insert("allow-oob".to_string(), oob.clone());
+                }
+            }
+            "event" => {
+                definition.insert("event".to_string(), Value::String(name.to_string()));
+                let data = self.arguments(string(info, "arg-type")?)?;
+                if !data.is_null() {
+                    definition.insert("data".to_string(), data);
+                }
+            }
+            other => return Err(format!("Unknown meta-type {} for {}", other, name)),
+        }
+        if !list(info, "features").is_empty() {
+            definition.insert("features".to_string(), info["features"].clone());
+        }
+        Ok(Some(Value::Object(definition)))
+    }
+}
+
+// The JSON type QEMU reports for each builtin
+fn json_type(builtin: &str) -> &'static str {
+    match builtin {
+        "str" | "QType" => "string",
+        "number" => "number",
+        "bool" => "boolean",
+        "null" => "null",
+        "any" => "value",
+        _ => "int",
+    }
+}
+
+// All the integer types look the same on the wire, so introspection only
+// knows about int
+fn type_key(value: &Value) -> String {
+    match value {
+        Value::String(s) if is_builtin(s) && json_type(s) == "int" => "int".to_string(),
+        Value::String(s) => s.clone(),
+        Value::Array(a) if a.len() == 1 => format!("[{}]", type_key(&a[0])),
+        _ => String::new(),
+    }
+}
+
+// The name QEMU gives a type in implicit object names, which unlike
+// type_key keeps the integer types apart
+fn declared_name(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Array(a) if a.len() == 1 => format!("{}List", declared_name(&a[0])),
+        _ => String::new(),
+    }
+}
+
+fn pairs(members: &Value) -> Vec<(String, Value)> {
+    match members {
+        Value::Object(o) => o.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn schema_info(name: String, meta_type: &str) -> Map<String, Value> {
+    let mut info = Map::new();
+    info.insert("name".to_string(), Value::String(name));
+    info.insert(
+        "meta-type".to_string(),
+        Value::String(meta_type.to_string()),
+    );
+    info
+}
+
+// Only feature names make it into introspection
+fn add_features(info: &mut Map<String, Value>, features: &Value) {
+    let features: Vec<Value> = names(features)
+        .into_iter()
+        .map(|f| Value::String(f.to_string()))
+        .collect();
+    if !features.is_empty() {
+        info.insert("features".to_string(), Value::Array(features));
+    }
+}
+
+// Enums list their values twice: the old plain list and the members that
+// can carry features
+fn add_values(info: &mut Map<String, Value>, values: &Value) {
+    let mut members = Vec::new();
+    if let Value::Array(a) = values {
+        for value in a {
+            let mut member = Map::new();
+            let name = match value {
+                &Value::String(_) => value.clone(),
+                _ => value.get("name").cloned().unwrap_or(Value::Null),
+            };
+            member.insert("name".to_string(), name);
+            add_features(&mut member, value.get("features").unwrap_or(&Value::Null));
+            members.push(Value::Object(member));
+        }
+    }
+    let plain = names(values)
+        .into_iter()
+        .map(|v| Value::String(v.to_string()))
+        .collect();
+    info.insert("values".to_string(), Value::Array(plain));
+    info.insert("members".to_string(), Value::Array(members));
+}
+
+// A type introspection still has to describe
+#[derive(Clone)]
+enum Pending {
+    Definition(String),
+    Builtin(String),
+    Array(Value),
+    // Implicit object types, like command arguments and the wrappers
+    // around simple union branches
+    Object(String, Vec<(String, Value)>),
+    // The implicit enum of a simple union's branch names
+    Kind(String, Value),
+}
+
+// Generates SchemaInfo the way QEMU's introspect.py does: commands and
+// events first, then every type they need in the order it was first used
+struct Introspector<'a> {
+    schema: &'a Schema,
+    mask: bool,
+    masked: HashMap<String, String>,
+    queued: HashSet<String>,
+    pending: Vec<Pending>,
+}
+
+impl<'a> Introspector<'a> {
+    // QEMU numbers the types in the order it first mentions them
+    fn name(&mut self, name: &str) -> String {
+        if !self.mask {
+            return name.to_string();
+        }
+        let next = self.masked.len().to_string();
+        self.masked.entry(name.to_string()).or_insert(next).clone()
+    }
+
+    fn queue(&mut self, key: String, pending: Pending) {
+        if self.queued.insert(key) {
+            self.pending.push(pending);
+        }
+    }
+
+    fn use_type(&mut self, value: &Value) -> Result<String, String> {
+        match value {
+            Value::String(s) if is_builtin(s) => {
+                let builtin = type_key(value);
+                self.queue(builtin.clone(), Pending::Builtin(builtin.clone()));
+                Ok(builtin)
+            }
+            Value::String(s) => {
+                if self.schema.get(s).is_none() {
+                    return Err(format!("{} is not defined in the schema", s));
+                }
+                self.queue(s.clone(), Pending::Definition(s.clone()));
+                Ok(self.name(s))
+            }
+            Value::Array(a) if a.len() == 1 => {
+                self.queue(type_key(value), Pending::Array(a[0].clone()));
+                Ok(format!("[{}]", self.use_type(&a[0])?))
+            }
+            _ => Err(format!("Not a type: {:?}", value)),
+        }
+    }
+
+    fn use_object(&mut self, name: String, members: Vec<(String, Value)>) -> String {
+        let masked = self.name(&name);
+        self.queue(name.clone(), Pending::Object(name, members));
+        masked
+    }
+
+    fn use_arguments(&mut self, owner: &str, data: &Value) -> Result<String, String> {
+        match *data {
+            Value::Null => Ok(self.use_object("q_empty".to_string(), Vec::new())),
+            Value::Object(_) => Ok(self.use_object(format!("q_obj_{}-arg", owner), pairs(data))),
+            _ => self.use_type(data),
+        }
+    }
+
+    fn use_returns(&mut self, returns: &Value) -> Result<String, String> {
+        match returns {
+            &Value::Null => Ok(self.use_object("q_empty".to_string(), Vec::new())),
+            _ => self.use_type(returns),
+        }
+    }
+
+    // Members of the base come before the type's own
+    fn flat_members(&self, base: &Value) -> Result<Vec<(String, Value)>, String> {
+        match base {
+            Value::String(s) => match self.schema.get(s) {
+                Some(QemuType::Struct(base)) => {
+                    let mut members = self.flat_members(&base.base)?;
+                    members.extend(pairs(&base.fields));
+                    Ok(members)
+                }
+                _ => Err(format!("Base {} is not a struct", s)),
+            },
+            _ => Ok(pairs(base)),
+        }
+    }
+
+    fn members(&mut self, members: &[(String, Value)]) -> Result<Value, String> {
+        let mut result = Vec::new();
+        for (key, value) in members {
+            let optional = key.starts_with('*');
+            let mut member = Map::new();
+            member.insert(
+                "name".to_string(),
+                Value::String(key.trim_start_matches('*').to_string()),
+            );
+            let member_type = self.use_type(member_type(value))?;
+            member.insert("type".to_string(), Value::String(member_type));
+            if optional {
+                member.insert("default".to_string(), Value::Null);
+            }
+            add_features(&mut member, value.get("features").unwrap_or(&Value::Null));
+            result.push(Value::Object(member));
+        }
+        Ok(Value::Array(result))
+    }
+
+    fn variant(&mut self, case: &str, branch: String) -> Value {
+        let mut variant = Map::new();
+        variant.insert("case".to_string(), Value::String(case.to_string()));
+        variant.insert("type".to_string(), Value::String(branch));
+        Value::Object(variant)
+    }
+
+    fn union(&mut self, info: &mut Map<String, Value>, union: &Union) -> Result<(), String> {
+        let mut variants = Vec::new();
+        match union.discriminator {
+            Value::String(ref tag) => {
+                let members = self.flat_members(&union.base)?;
+                let value = self.members(&members)?;
+                info.insert("members".to_string(), value);
+                info.insert("tag".to_string(), Value::String(tag.clone()));
+                for (case, branch) in pairs(&union.data) {
+                    let branch = self.use_type(member_type(&branch))?;
+                    variants.push(self.variant(&case, branch));
+                }
+                // Enum values without a branch get an empty one
+                let tag_type = members
+                    .iter()
+                    .find(|m| m.0.trim_start_matches('*') == tag)
+                    .and_then(|m| member_type(&m.1).as_str());
+                if let Some(QemuType::Enum(e)) = tag_type.and_then(|t| self.schema.get(t)) {
+                    for value in names(&e.fields) {
+                        if union.data.get(value).is_none() {
+                            let empty = self.use_object("q_empty".to_string(), Vec::new());
+                            variants.push(self.variant(value, empty));
+                        }
+                    }
+                }
+            }
+            _ => {
+                // Simple unions get an implicit enum for the tag and wrap
+                // each branch in an object with a single 'data' member
+                let kind = format!("{}Kind", union.name);
+                let cases = Value::Array(
+                    pairs(&union.data)
+                        .into_iter()
+                        .map(|(case, _)| Value::String(case))
+                        .collect(),
+                );
+                self.queue(kind.clone(), Pending::Kind(kind.clone(), cases));
+                let mut tag = Map::new();
+                tag.insert("name".to_string(), Value::String("type".to_string()));
+                tag.insert("type".to_string(), Value::String(self.name(&kind)));
+                info.insert(
+                    "members".to_string(),
+                    Value::Array(vec![Value::Object(tag)]),
+                );
+                info.insert("tag".to_string(), Value::String("type".to_string()));
+                for (case, branch) in pairs(&union.data) {
+                    let wrapped = member_type(&branch).clone();
+                    let wrapper = format!("q_obj_{}-wrapper", declared_name(&wrapped));
+                    let wrapper = self.use_object(wrapper, vec![("data".to_string(), wrapped)]);
+                    variants.push(self.variant(&case, wrapper));
+                }
+            }
+        }
+        info.insert("variants".to_string(), Value::Array(variants));
+        Ok(())
+    }
+
+    fn definition(&mut self, name: &str) -> Result<Map<String, Value>, String> {
+        let schema = self.schema;
+        let masked = self.name(name);
+        let (mut info, features) = match schema.get(name) {
+            Some(QemuType::Struct(s)) => {
+                let mut info = schema_info(masked, "object");
+                let mut members = self.flat_members(&s.base)?;
+                members.extend(pairs(&s.fields));
+                let members = self.members(&members)?;
+                info.insert("members".to_string(), members);
+                (info, &s.features)
+            }
+            Some(QemuType::Union(u)) => {
+                let mut info = schema_info(masked, "object");
+                self.union(&mut info, u)?;
+                (info, &u.features)
+            }
+            Some(QemuType::Alternate(a)) => {
+                let mut info = schema_info(masked, "alternate");
+                let mut members = Vec::new();
+                for (_, branch) in pairs(&a.data) {
+                    let mut member = Map::new();
+                    let branch = self.use_type(member_type(&branch))?;
+                    member.insert("type".to_string(), Value::String(branch));
+                    members.push(Value::Object(member));
+                }
+                info.insert("members".to_string(), Value::Array(members));
+                (info, &a.features)
+            }
+            Some(QemuType::Enum(e)) => {
+                let mut info = schema_info(masked, "enum");
+                add_values(&mut info, &e.fields);
+                (info, &e.features)
+            }
+            _ => return Err(format!("{} is not a type", name)),
+        };
+        add_features(&mut info, features);
+        Ok(info)
+    }
+
+    fn schema_info(&mut self, pending: Pending) -> Result<Value, String> {
+        let info = match pending {
+            Pending::Definition(name) => self.definition(&name)?,
+            Pending::Builtin(name) => {
+                let mut info = schema_info(name.clone(), "builtin");
+                info.insert(
+                    "json-type".to_string(),
+                    Value::String(json_type(&name).to_string()),
+                );
+                info
+            }
+            Pending::Array(element) => {
+                let element = self.use_type(&element)?;
+                let mut info = schema_info(format!("[{}]", element), "array");
+                info.insert("element-type".to_string(), Value::String(element));
+                info
+            }
+            Pending::Object(name, members) => {
+                let mut info = schema_info(self.name(&name), "object");
+                let members = self.members(&members)?;
+                info.insert("members".to_string(), members);
+                info
+            }
+            Pending::Kind(name, values) => {
+                let mut info = schema_info(self.name(&name), "enum");
+                add_values(&mut info, &values);
+                info
+            }
+        };
+        Ok(Value::Object(info))
+    }
+}
+
+impl Schema {
+    /// Build the schema from the output of QEMU's query-qmp-schema, either
+    /// the bare SchemaInfo list or the whole QMP reply.  Implicit argument
+    /// and event data types are written inline again, builtins and array
+    /// types go away, and every other type keeps the (possibly masked) name
+    /// QEMU gave it since the real one is lost.
+    pub fn from_introspection(input: &Value) -> Result<Schema, String> {
+        let introspection = Introspection::new(input)?;
+        let mut definitions = Vec::new();
+        for info in &introspection.entities {
+            if let Some(definition) = introspection.definition(info)? {
+                definitions.push(definition);
+            }
+        }
+        Schema::from_definitions(&definitions)
+    }
+
+    /// The SchemaInfo list a QEMU built from this schema answers
+    /// query-qmp-schema with, generated the same way as introspect.py.
+    /// With `mask` the type names are replaced by numbers like QEMU does.
+    /// 'if' conditions don't show up in the output, so configure the
+    /// schema first to get what a particular build reports.
+    pub fn introspect(&self, mask: bool) -> Result<Value, String> {
+        let mut introspector = Introspector {
+            schema: self,
+            mask,
+            masked: HashMap::new(),
+            queued: HashSet::new(),
+            pending: Vec::new(),
+        };
+        let mut entities = Vec::new();
+        for definition in self.definitions() {
+            let (mut info, features) = match definition {
+                QemuType::Command(c) => {
+                    let mut info = schema_info(c.name.clone(), "command");
+                    let arguments = introspector.use_arguments(&c.name, &c.fields)?;
+                    info.insert("arg-type".to_string(), Value::String(arguments));
+                    let returns = introspector.use_returns(&c.returns)?;
+                    info.insert("ret-type".to_string(), Value::String(returns));
+                    if c.allow_oob == Value::Bool(true) {
+                        info.insert("allow-oob".to_string(), Value::Bool(true));
+                    }
+                    (info, &c.features)
+                }
+                QemuType::Event(e) => {
+                    let mut info = schema_info(e.name.clone(), "event");
+                    let data = introspector.use_arguments(&e.name, &e.data)?;
+                    info.insert("arg-type".to_string(), Value::String(data));
+                    (info, &e.features)
+                }
+                _ => continue,
+            };
+            add_features(&mut info, features);
+            entities.push(Value::Object(info));
+        }
+        // Describing a type can use more types, which end up at the back
+        let mut i = 0;
+        while i < introspector.pending.len() {
+            let pending = introspector.pending[i].clone();
+            entities.push(introspector.schema_info(pending)?);
+            i += 1;
+        }
+        Ok(Value::Array(entities))
+    }
+}
+
+#[test]
+fn test_from_introspection() {
+    let input = r#"{"return": [
+{"name": "query-status", "meta-type": "command", "arg-type": "0", "ret-type": "1",
+ "allow-oob": true},
+{"name": "set-mode", "meta-type": "command", "arg-type": "2", "ret-type": "0",
+ "features": ["deprecated"]},
+{"name": "STOP", "meta-type": "event", "arg-type": "0"},
+{"name": "0", "meta-type": "object", "members": []},
+{"name": "1", "meta-type": "object",
+ "members": [{"name": "running", "type": "bool"},
+             {"name": "status", "type": "3"},
+             {"name": "reasons", "type": "[str]", "default": null}]},
+{"name": "2", "meta-type": "object",
+ "members": [{"name": "mode", "type": "3", "default": null,
+              "features": ["unstable"]}]},
+{"name": "3", "meta-type": "enum", "values": ["running", "paused"],
+ "members": [{"name": "running"}, {"name": "paused", "features": ["deprecated"]}]},
+{"name": "4", "meta-type": "object",
+ "members": [{"name": "type", "type": "3"}], "tag": "type",
+ "variants": [{"case": "running", "type": "1"}, {"case": "paused", "type": "0"}]},
+{"name": "5", "meta-type": "alternate", "members": [{"type": "str"}, {"type": "4"}]},
+{"name": "bool", "meta-type": "builtin", "json-type": "boolean"},
+{"name": "str", "meta-type": "builtin", "json-type": "string"},
+{"name": "[str]", "meta-type": "array", "element-type": "str"}
+]}"#;
+    let schema = Schema::from_introspection(&serde_json::from_str(input).unwrap()).unwrap();
+    let names: Vec<&str> = schema
+        .definitions()
+        .iter()
+        .filter_map(|d| d.name())
+        .collect();
+    assert_eq!(
+        names,
+        vec!["query-status", "set-mode", "STOP", "1", "3", "4", "5"]
+    );
+
+    match schema.get("query-status") {
+        Some(QemuType::Command(c)) => {
+            assert!(c.fields.is_null());
+            assert_eq!(c.returns, Value::String("1".to_string()));
+        }
+        other => panic!("Expected a command: {:?}", other),
+    }
+    match schema.get("set-mode") {
+        Some(QemuType::Command(c)) => {
+            assert_eq!(
+                c.fields,
+                serde_json::from_str::<Value>(
+                    r#"{"*mode": {"type": "3", "features": ["unstable"]}}"#
+                )
+                .unwrap()
+            );
+            assert!(c.returns.is_null());
+            assert_eq!(
+                c.features,
+                serde_json::from_str::<Value>(r#"["deprecated"]"#).unwrap()
+            );
+        }
+        other => panic!("Expected a command: {:?}", other),
+    }
+    match schema.get("1") {
+        Some(QemuType::Struct(s)) => {
+            assert_eq!(
+                s.fields["*reasons"],
+                serde_json::from_str::<Value>(r#"["str"]"#).unwrap()
+            )
+        }
+        other => panic!("Expected a struct: {:?}", other),
+    }
+    match schema.get("4") {
+        Some(QemuType::Union(u)) => {
+            assert_eq!(u.discriminator, Value::String("type".to_string()));
+            assert_eq!(
+                u.data,
+                serde_json::from_str::<Value>(r#"{"running": "1"}"#).unwrap()
+            );
+        }
+        other => panic!("Expected a union: {:?}", other),
+    }
+    assert!(
+        Schema::from_introspection(&serde_json::from_str(r#"[{"name": "x"}]"#).unwrap()).is_err()
+    );
+}
+
+#[test]
+fn test_introspect() {
+    let input = r#"
+{ 'enum': 'RunState', 'data': [ 'running', { 'name': 'paused', 'features': [ 'deprecated' ] } ] }
+{ 'struct': 'StatusInfo', 'data': { 'status': 'RunState', '*ids': [ 'uint8' ] } }
+{ 'union': 'Event', 'base': { 'kind': 'RunState' }, 'discriminator': 'kind',
+  'data': { 'running': 'StatusInfo' } }
+{ 'command': 'query-status', 'returns': 'StatusInfo', 'allow-oob': true }
+{ 'event': 'STOP', 'data': { 'event': 'Event' }, 'features': [ 'unstable' ] }
+"#;
+    let schema = Schema::parse(input).unwrap();
+    let expected = r#"[
+{"name": "query-status", "meta-type": "command", "arg-type": "0", "ret-type": "1",
+ "allow-oob": true},
+{"name": "STOP", "meta-type": "event", "arg-type": "2", "features": ["unstable"]},
+{"name": "0", "meta-type": "object", "members": []},
+{"name": "1", "meta-type": "object",
+ "members": [{"name": "status", "type": "3"},
+             {"name": "ids", "type": "[int]", "default": null}]},
+{"name": "2", "meta-type": "object", "members": [{"name": "event", "type": "4"}]},
+{"name": "3", "meta-type": "enum", "values": ["running", "paused"],
+ "members": [{"name": "running"}, {"name": "paused", "features": ["deprecated"]}]},
+{"name": "[int]", "meta-type": "array", "element-type": "int"},
+{"name": "int", "meta-type": "builtin", "json-type": "int"},
+{"name": "4", "meta-type": "object", "members": [{"name": "kind", "type": "3"}],
+ "tag": "kind",
+ "variants": [{"case": "running", "type": "1"}, {"case": "paused", "type": "0"}]}
+]"#;
+    let introspection = schema.introspect(true).unwrap();
+    assert_eq!(
+        introspection,
+        serde_json::from_str::<Value>(expected).unwrap()
+    );
+
+    // Loading it again gives back the same commands and events
+    let loaded = Schema::from_introspection(&introspection).unwrap();
+    assert_eq!(loaded.introspect(true).unwrap(), introspection);
+
+    let unmasked = schema.introspect(false).unwrap();
+    assert_eq!(
+        unmasked[0]["ret-type"],
+        Value::String("StatusInfo".to_string())
+    );
+    assert_eq!(
+        unmasked[1]["arg-type"],
+        Value::String("q_obj_STOP-arg".to_string())
+    );
+}
+
+#[test]
+fn test_introspect_simple_union() {
+    let input = r#"
+{ 'union': 'Value', 'data': { 'small': 'uint8', 'big': 'int64', 'list': [ 'uint8' ] } }
+{ 'command': 'get-value', 'returns': 'Value' }
+"#;
+    let schema = Schema::parse(input).unwrap();
+    let introspection = schema.introspect(false).unwrap();
+    let names: Vec<&str> = introspection
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|info| info["name"].as_str())
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            "get-value",
+            "q_empty",
+            "Value",
+            "ValueKind",
+            "q_obj_uint8-wrapper",
+            "q_obj_int64-wrapper",
+            "q_obj_uint8List-wrapper",
+            "int",
+            "[int]",
+        ]
+    );
+    let wrapper = introspection
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|info| info["name"] == "q_obj_uint8List-wrapper")
+        .unwrap();
+    assert_eq!(
+        wrapper["members"],
+        serde_json::from_str::<Value>(r#"[{"name": "data", "type": "[int]"}]"#).unwrap()
+    );
+}
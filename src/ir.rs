@@ -0,0 +1,482 @@
+//! A JSON form of the schema for tools written in other languages.
+//!
+//! The document looks like `{ "version": 1, "definitions": [...] }`.  Every
+//! definition has these keys:
+//!
+//! * `meta-type`: "struct", "union", "alternate", "enum", "command",
+//!   "event" or "include"
+//! * `name`: the name of the definition, or the included file
+//! * `module`: the file it was defined in, or null
+//! * `doc`: the lines of its doc comment without the leading '#'
+//! * `if`: the condition exactly as written in the schema, or null
+//! * `features`: a list of `{ "name": ..., "if": ... }`, `if` being optional
+//!
+//! Members are `{ "name", "type", "array", "optional", "if", "features" }`
+//! with the name free of the '*' marker and `array` set for `['type']`.
+//! The rest depends on the meta-type:
+//!
+//! * struct: `base` (a name or null) and `members`
+//! * union: `base` (a name or null), `base-members` for an inline base,
+//!   `discriminator` (null for simple unions) and `branches`, a list of
+//!   `{ "case", "type", "array", "if" }`
+//! * alternate: `branches` like unions
+//! * enum: `values`, a list of `{ "name", "if", "features" }`
+//! * command: `arguments-type` (a name or null), `arguments` for inline
+//!   arguments, `returns` (null or `{ "type", "array" }`), `allow-oob`,
+//!   `success-response` and `gen`
+//! * event: `data-type` (a name or null) and `data` for inline data
+//!
+//! The version goes up whenever any of this changes in a way existing
+//! readers would trip over.
+
+extern crate serde;
+extern crate serde_json;
+
+use self::serde::de::Error;
+use self::serde::{Deserialize, Deserializer, Serialize, Serializer};
+use self::serde_json::{Map, Value};
+
+use schema::{member_type, Schema};
+use {Alternate, Command, Enum, Event, QemuType, Section, Struct, Union};
+
+/// The version of the JSON form written by `Schema::to_ir`
+pub const IR_VERSION: u64 = 1;
+
+fn object(entries: Vec<(&str, Value)>) -> Value {
+    let mut map = Map::new();
+    for (key, value) in entries {
+        map.insert(key.to_string(), value);
+    }
+    Value::Object(map)
+}
+
+fn string(value: &str) -> Value {
+    Value::String(value.to_string())
+}
+
+fn long_form(value: &Value, key: &str) -> Value {
+    match value {
+        Value::Object(o) => o.get(key).cloned().unwrap_or(Value::Null),
+        _ => Value::Null,
+    }
+}
+
+// A type reference, with arrays flagged instead of nested
+fn type_ir(value: &Value) -> (Value, bool) {
+    match value {
+        Value::Array(a) if a.len() == 1 => (a[0].clone(), true),
+        _ => (value.clone(), false),
+    }
+}
+
+fn type_from_ir(ir: &Value) -> Value {
+    if ir["array"] == Value::Bool(true) {
+        Value::Array(vec![ir["type"].clone()])
+    } else {
+        ir["type"].clone()
+    }
+}
+
+// Features and enum values are plain names unless they have a condition
+// or features of their own
+fn named_ir(value: &Value) -> Value {
+    match value {
+        Value::String(s) => object(vec![("name", string(s))]),
+        _ => value.clone(),
+    }
+}
+
+fn named_from_ir(ir: &Value) -> Value {
+    match ir {
+        Value::Object(o) if o.len() == 1 => o.get("name").cloned().unwrap_or(Value::Null),
+        _ => ir.clone(),
+    }
+}
+
+fn list_ir(list: &Value) -> Value {
+    match list {
+        Value::Array(a) => Value::Array(a.iter().map(named_ir).collect()),
+        _ => Value::Array(Vec::new()),
+    }
+}
+
+fn list_from_ir(ir: &Value) -> Value {
+    match ir {
+        Value::Array(a) if !a.is_empty() => {
+            Value::Array(a.iter().map(named_from_ir).collect())
+        }
+        _ => Value::Null,
+    }
+}
+
+fn members_ir(members: &Value) -> Value {
+    let mut result = Vec::new();
+    if let Value::Object(o) = members {
+        for (key, value) in o {
+            let (member_type, array) = type_ir(member_type(value));
+            result.push(object(vec![
+                ("name", string(key.trim_start_matches('*'))),
+                ("type", member_type),
+                ("array", Value::Bool(array)),
+                ("optional", Value::Bool(key.starts_with('*'))),
+                ("if", long_form(value, "if")),
+                ("features", list_ir(&long_form(value, "features"))),
+            ]));
+        }
+    }
+    Value::Array(result)
+}
+
+fn members_from_ir(ir: &Value) -> Value {
+    let mut members = Map::new();
+    for m in ir.as_array().map(|a| a.as_slice()).unwrap_or(&[]) {
+        let name = m["name"].as_str().unwrap_or("");
+        let key = if m["optional"] == Value::Bool(true) {
+            format!("*{}", name)
+        } else {
+            name.to_string()
+        };
+        let member_type = type_from_ir(m);
+        let features = list_from_ir(&m["features"]);
+        let value = if m["if"].is_null() && features.is_null() {
+            member_type
+        } else {
+            let mut long = vec![("type", member_type)];
+            if !m["if"].is_null() {
+                long.push(("if", m["if"].clone()));
+            }
+            if !features.is_null() {
+                long.push(("features", features));
+            }
+            object(long)
+        };
+        members.insert(key, value);
+    }
+    Value::Object(members)
+}
+
+fn branches_ir(branches: &Value) -> Value {
+    let mut result = Vec::new();
+    if let Value::Object(o) = branches {
+        for (case, value) in o {
+            let (branch_type, array) = type_ir(member_type(value));
+            result.push(object(vec![
+                ("case", string(case)),
+                ("type", branch_type),
+                ("array", Value::Bool(array)),
+                ("if", long_form(value, "if")),
+            ]));
+        }
+    }
+    Value::Array(result)
+}
+
+fn branches_from_ir(ir: &Value) -> Value {
+    let mut branches = Map::new();
+    for b in ir.as_array().map(|a| a.as_slice()).unwrap_or(&[]) {
+        let branch_type = type_from_ir(b);
+        let value = if b["if"].is_null() {
+            branch_type
+        } else {
+            object(vec![("type", branch_type), ("if", b["if"].clone())])
+        };
+        branches.insert(b["case"].as_str().unwrap_or("").to_string(), value);
+    }
+    Value::Object(branches)
+}
+
+// A named type goes in `<key>-type`, inline members under `key`
+fn split_members(value: &Value) -> (Value, Value) {
+    match value {
+        &Value::String(_) => (value.clone(), Value::Array(Vec::new())),
+        _ => (Value::Null, members_ir(value)),
+    }
+}
+
+fn join_members(named: &Value, members: &Value) -> Value {
+    if named.is_string() {
+        return named.clone();
+    }
+    match members {
+        Value::Array(a) if !a.is_empty() => members_from_ir(members),
+        _ => Value::Null,
+    }
+}
+
+fn definition_ir(section: &Section) -> Value {
+    let (meta_type, mut entries, condition, features) = match section.qemu_type {
+        QemuType::Struct(ref s) => (
+            "struct",
+            vec![
+                ("name", string(&s.name)),
+                ("base", s.base.clone()),
+                ("members", members_ir(&s.fields)),
+            ],
+            &s.condition,
+            &s.features,
+        ),
+        QemuType::Union(ref u) => {
+            let (base, base_members) = split_members(&u.base);
+            (
+                "union",
+                vec![
+                    ("name", string(&u.name)),
+                    ("base", base),
+                    ("base-members", base_members),
+                    ("discriminator", u.discriminator.clone()),
+                    ("branches", branches_ir(&u.data)),
+                ],
+                &u.condition,
+                &u.features,
+            )
+        }
+        QemuType::Alternate(ref a) => (
+            "alternate",
+            vec![
+                ("name", string(&a.name)),
+                ("branches", branches_ir(&a.data)),
+            ],
+            &a.condition,
+            &a.features,
+        ),
+        QemuType::Enum(ref e) => (
+            "enum",
+            vec![("name", string(&e.name)), ("values", list_ir(&e.fields))],
+            &e.condition,
+            &e.features,
+        ),
+        QemuType::Command(ref c) => {
+            let (arguments_type, arguments) = split_members(&c.fields);
+            let returns = if c.returns.is_null() {
+                Value::Null
+            } else {
+                let (returns, array) = type_ir(&c.returns);
+                object(vec![("type", returns), ("array", Value::Bool(array))])
+            };
+            (
+                "command",
+                vec![
+                    ("name", string(&c.name)),
+                    ("arguments-type", arguments_type),
+                    ("arguments", arguments),
+                    ("returns", returns),
+                    ("allow-oob", Value::Bool(c.allow_oob == Value::Bool(true))),
+                    (
+                        "success-response",
+                        Value::Bool(c.success_response != Value::Bool(false)),
+                    ),
+                    ("gen", Value::Bool(c.gen != Value::Bool(false))),
+                ],
+                &c.condition,
+                &c.features,
+            )
+        }
+        QemuType::Event(ref e) => {
+            let (data_type, data) = split_members(&e.data);
+            (
+                "event",
+                vec![
+                    ("name", string(&e.name)),
+                    ("data-type", data_type),
+                    ("data", data),
+                ],
+                &e.condition,
+                &e.features,
+            )
+        }
+        QemuType::Include { ref name } => (
+            "include",
+            vec![("name", string(name))],
+            &Value::Null,
+            &Value::Null,
+        ),
+        QemuType::Unknown => ("unknown", Vec::new(), &Value::Null, &Value::Null),
+    };
+    entries.insert(0, ("meta-type", string(meta_type)));
+    entries.push((
+        "module",
+        section.module.as_ref().map_or(Value::Null, |m| string(m)),
+    ));
+    entries.push((
+        "doc",
+        Value::Array(section.description.iter().map(|l| string(l)).collect()),
+    ));
+    entries.push(("if", condition.clone()));
+    entries.push(("features", list_ir(features)));
+    object(entries)
+}
+
+fn definition_from_ir(ir: &Value) -> Result<Section, String> {
+    let name = ir["name"].as_str().unwrap_or("").to_string();
+    let condition = ir["if"].clone();
+    let features = list_from_ir(&ir["features"]);
+    let qemu_type = match ir["meta-type"].as_str() {
+        Some("struct") => QemuType::Struct(Struct {
+            name,
+            fields: members_from_ir(&ir["members"]),
+            base: ir["base"].clone(),
+            condition,
+            features,
+        }),
+        Some("union") => QemuType::Union(Union {
+            name,
+            base: join_members(&ir["base"], &ir["base-members"]),
+            discriminator: ir["discriminator"].clone(),
+            data: branches_from_ir(&ir["branches"]),
+            condition,
+            features,
+        }),
+        Some("alternate") => QemuType::Alternate(Alternate {
+            name,
+            data: branches_from_ir(&ir["branches"]),
+            condition,
+            features,
+        }),
+        Some("enum") => QemuType::Enum(Enum {
+            name,
+            fields: match list_from_ir(&ir["values"]) {
+                Value::Null => Value::Array(Vec::new()),
+                values => values,
+            },
+            condition,
+            features,
+        }),
+        Some("command") => QemuType::Command(Command {
+            name,
+            fields: join_members(&ir["arguments-type"], &ir["arguments"]),
+            gen: if ir["gen"] == Value::Bool(false) {
+                Value::Bool(false)
+            } else {
+                Value::Null
+            },
+            returns: if ir["returns"].is_null() {
+                Value::Null
+            } else {
+                type_from_ir(&ir["returns"])
+            },
+            allow_oob: if ir["allow-oob"] == Value::Bool(true) {
+                Value::Bool(true)
+            } else {
+                Value::Null
+            },
+            success_response: if ir["success-response"] == Value::Bool(false) {
+                Value::Bool(false)
+            } else {
+                Value::Null
+            },
+            condition,
+            features,
+        }),
+        Some("event") => QemuType::Event(Event {
+            name,
+            data: join_members(&ir["data-type"], &ir["data"]),
+            condition,
+            features,
+        }),
+        Some("include") => QemuType::Include { name },
+        Some("unknown") => QemuType::Unknown,
+        _ => return Err(format!("Unknown meta-type in {}", ir)),
+    };
+    Ok(Section {
+        description: ir["doc"]
+            .as_array()
+            .map(|a| {
+                a.iter()
+                    .filter_map(|l| l.as_str().map(|l| l.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        qemu_type,
+        module: ir["module"].as_str().map(|m| m.to_string()),
+    })
+}
+
+impl Schema {
+    /// The schema in the versioned JSON form described in the [`ir`](crate::ir)
+    /// module
+    pub fn to_ir(&self) -> Value {
+        object(vec![
+            ("version", Value::from(IR_VERSION)),
+            (
+                "definitions",
+                Value::Array(self.sections.iter().map(definition_ir).collect()),
+            ),
+        ])
+    }
+
+    /// Read the schema back from its JSON form
+    pub fn from_ir(ir: &Value) -> Result<Schema, String> {
+        match ir["version"].as_u64() {
+            Some(IR_VERSION) => {}
+            Some(v) => return Err(format!("Unsupported schema IR version {}", v)),
+            None => return Err("Schema IR without a version".to_string()),
+        }
+        let definitions = match ir["definitions"] {
+            Value::Array(ref a) => a,
+            _ => return Err("Schema IR without definitions".to_string()),
+        };
+        Ok(Schema::from_sections(
+            definitions
+                .iter()
+                .map(definition_from_ir)
+                .collect::<Result<Vec<Section>, String>>()?,
+        ))
+    }
+}
+
+impl Serialize for Schema {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_ir().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Schema {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let ir = Value::deserialize(deserializer)?;
+        Schema::from_ir(&ir).map_err(D::Error::custom)
+    }
+}
+
+#[test]
+fn test_ir() {
+    let input = r#"{ 'include': 'common.json' }
+{ 'enum': 'RunState',
+  'data': [ 'running', { 'name': 'debug', 'if': 'CONFIG_DEBUG' } ] }
+{ 'struct': 'StatusInfo', 'base': 'Base',
+  'data': { 'status': 'RunState', '*ids': [ 'int' ],
+            'x': { 'type': 'str', 'features': [ 'unstable' ] } } }
+{ 'union': 'Event', 'base': { 'kind': 'RunState' }, 'discriminator': 'kind',
+  'data': { 'running': 'StatusInfo' } }
+{ 'alternate': 'Id', 'data': { 'n': 'int', 's': 'str' } }
+{ 'command': 'query-status', 'returns': 'StatusInfo', 'allow-oob': true,
+  'features': [ 'deprecated' ] }
+{ 'command': 'stop', 'data': 'StatusInfo', 'gen': false,
+  'success-response': false, 'if': 'CONFIG_STOP' }
+{ 'event': 'STOP', 'data': { 'reason': 'str' } }
+"#;
+    let schema = Schema::parse(input).unwrap();
+    let ir = schema.to_ir();
+    assert_eq!(ir["version"], Value::from(1));
+    let status = &ir["definitions"][2];
+    assert_eq!(status["meta-type"], string("struct"));
+    assert_eq!(
+        status["members"][1],
+        serde_json::from_str::<Value>(
+            r#"{"name": "ids", "type": "int", "array": true, "optional": true,
+                "if": null, "features": []}"#
+        )
+        .unwrap()
+    );
+    assert_eq!(
+        ir["definitions"][5]["returns"],
+        serde_json::from_str::<Value>(r#"{"type": "StatusInfo", "array": false}"#).unwrap()
+    );
+    assert_eq!(Schema::from_ir(&ir).unwrap(), schema);
+
+    let text = serde_json::to_string(&schema).unwrap();
+    assert_eq!(serde_json::from_str::<Schema>(&text).unwrap(), schema);
+
+    let mut future = ir.clone();
+    future["version"] = Value::from(2);
+    assert!(Schema::from_ir(&future).is_err());
+}
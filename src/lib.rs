@@ -1,85 +1,67 @@
 #[macro_use]
-extern crate lazy_static;
-#[macro_use]
 extern crate nom;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
 extern crate serde;
 extern crate serde_json;
 
 use std::str::from_utf8;
 
+mod codegen;
+mod compat;
+mod condition;
+mod cst;
+mod diff;
+mod edit;
+mod format;
+mod graph;
+mod introspect;
+pub mod ir;
+mod markup;
+mod policy;
+mod pretty;
+mod prune;
+mod schema;
 mod serde_parser;
+mod version;
+mod visitor;
+pub use codegen::TypeMap;
+pub use compat::{Classification, Compatibility};
+pub use condition::Condition;
+pub use cst::{Element, Member, Node, SyntaxTree, Token, TokenKind, Trivia};
+pub use diff::Change;
+pub use format::{check_format, format_source};
+pub use graph::{Dependency, DependencyGraph, DependencyKind};
+pub use ir::IR_VERSION;
+pub use policy::{CompatPolicy, InputPolicy, OutputPolicy};
+pub use schema::Schema;
 pub use serde_parser::generate_rust_definitions;
+pub use version::{QemuVersion, SinceIndex};
+pub use visitor::SchemaVisitor;
 
 use nom::multispace;
-use serde::de::DeserializeOwned;
 
 use serde_json::Value;
-use std::collections::HashMap;
-
-// Qemu unfortunately has some variable names that are Rust reserved words
-lazy_static! {
-    static ref REPLACEMAP: HashMap<String, String> = {
-        let mut m = HashMap::new();
-        m.insert("type".to_string(), "qemu_type".to_string());
-        m.insert("in".to_string(), "qemu_in".to_string());
-        m.insert("abstract".to_string(), "qemu_abstract".to_string());
-        m.insert("String".to_string(), "qemu_string".to_string());
-        m.insert("str".to_string(), "String".to_string());
-        m.insert("int".to_string(), "i64".to_string());
-        m.insert("0".to_string(), "qemu_0".to_string());
-        m.insert("1".to_string(), "qemu_1".to_string());
-        m.insert("2".to_string(), "qemu_2".to_string());
-        m.insert("3".to_string(), "qemu_3".to_string());
-        m.insert("4".to_string(), "qemu_4".to_string());
-        m.insert("5".to_string(), "qemu_5".to_string());
-        m.insert("6".to_string(), "qemu_6".to_string());
-        m.insert("7".to_string(), "qemu_7".to_string());
-        m.insert("8".to_string(), "qemu_8".to_string());
-        m.insert("9".to_string(), "qemu_9".to_string());
-        m
-    };
-}
-
-fn call_qemu<T: DeserializeOwned>(v: serde_json::Value) -> Result<T, String> {
-    let s = "";
-    let result = serde_json::from_str::<T>(s).map_err(|e| e.to_string())?;
 
-    Ok(result)
-}
-
-fn sanitize_name(name: &String) -> String {
-    println!("sanitize_name: {}", name);
-    let safe_name = name.replace("-", "_").replace("*", "").replace(".", "_");
-
-    if REPLACEMAP.contains_key(&safe_name) {
-        REPLACEMAP.get(&safe_name).unwrap().clone()
-    } else {
-        safe_name
-    }
-}
-
-named!(blanks, chain!(many0!(multispace), || &b""[..]));
+named!(blanks, do_parse!(many0!(multispace) >> (&b""[..])));
 
 named!(comment_block<&[u8], Vec<String> >,
-    chain!(
-        comments: many0!(comment_line),
-        ||{
-            comments
-        }
+    do_parse!(
+        comments: many0!(comment_line) >>
+        (comments)
     )
 );
 
 named!(comment_line<&[u8], String>,
-    chain!(
-        tag!("#") ~
+    do_parse!(
+        tag!("#") >>
 // alt!(
 //    tag!("#")
 //    | tag!("\n")
-//    | tag!(" ")) ~
-        line: map_res!(take_until_and_consume!("\n"), from_utf8),
-        ||{
-            line.to_string()
-        }
+//    | tag!(" ")) >>
+        line: map_res!(take_until_and_consume!("\n"), from_utf8) >>
+        (line.to_string())
     )
 );
 
@@ -87,10 +69,8 @@ named!(comment_line<&[u8], String>,
 pub fn find_element(input: &[u8]) -> nom::IResult<&[u8], String> {
     let mut brace_count = 0;
     let mut count = 0;
-    loop {
-        if input.len() == 0 {
-            break;
-        }
+    // An unbalanced element runs to the end of the input
+    while count < input.len() {
         if input[count] as char == '{' {
             brace_count += 1;
             count += 1;
@@ -171,8 +151,6 @@ fn test_remove_comments() {
 
 #[test]
 fn test_comment_parsing() {
-    let x: &[u8] = &[];
-
     let input = r#"##
 # @query-vnc:
 #
@@ -186,334 +164,436 @@ fn test_comment_parsing() {
     println!("test_comment_parsing Result: {:?}", result);
 }
 
+#[test]
+fn test_description_parsing() {
+    let input = r#"##
+# @query-vnc:
+#
+# Returns information about the current VNC server
+#
+# @verbose: #optional include every client
+#           (since 2.3)
+#
+# Returns: @VncInfo
+#
+# Since: 0.14.0
+##
+"#;
+    let comments = match comment_block(input.as_bytes()) {
+        nom::IResult::Done(_, c) => c,
+        other => panic!("Failed to parse comments: {:?}", other),
+    };
+    let description = Description::parse(&comments);
+    assert_eq!(description.name, "query-vnc");
+    assert_eq!(
+        description.parameters,
+        Some(vec![(
+            "verbose".to_string(),
+            "#optional include every client (since 2.3)".to_string(),
+        )])
+    );
+    assert_eq!(description.returns, Some("@VncInfo".to_string()));
+    assert_eq!(description.version_since, Some(QemuVersion::new(0, 14, 0)));
+    assert_eq!(
+        description.body,
+        vec!["Returns information about the current VNC server"]
+    );
+
+    let input = r#"##
+# @block_passwd:
+#
+# This command sets the password of a block device that has not been open
+# with a password and requires one.
+#
+# The two cases where this can happen are a block device is created through
+# QEMU's initial command line or a block device is changed.
+#
+# @device: the name of the device
+#
+# Returns: nothing on success
+#          If @device is not a valid block device, DeviceNotFound
+#          If @device is not encrypted, DeviceNotEncrypted
+#
+# Example:
+#
+# -> { "execute": "block_passwd",
+#      "arguments": { "device": "ide0-hd0" } }
+# <- { "return": {} }
+#
+# Since: 0.14.0
+##
+"#;
+    let comments = match comment_block(input.as_bytes()) {
+        nom::IResult::Done(_, c) => c,
+        other => panic!("Failed to parse comments: {:?}", other),
+    };
+    let description = Description::parse(&comments);
+    assert_eq!(description.body.len(), 5);
+    assert_eq!(description.body[2], "");
+    assert_eq!(description.returns, Some("nothing on success".to_string()));
+    assert_eq!(
+        description.errors,
+        vec![
+            "If @device is not a valid block device, DeviceNotFound",
+            "If @device is not encrypted, DeviceNotEncrypted",
+        ]
+    );
+    assert_eq!(
+        description.example,
+        Some(vec![
+            "-> { \"execute\": \"block_passwd\",".to_string(),
+            "     \"arguments\": { \"device\": \"ide0-hd0\" } }".to_string(),
+            "<- { \"return\": {} }".to_string(),
+        ])
+    );
+    assert_eq!(description.version_since, Some(QemuVersion::new(0, 14, 0)));
+
+    let input = r#"##
+# @transaction:
+#
+# Errors:
+#     - Any errors from commands in the transaction.  If one fails,
+#       none of them are applied
+#     - If @actions is empty, GenericError
+#
+# Example:
+#
+# -> { "execute": "transaction", "arguments": { "actions": [] } }
+# <- { "error": {} }
+#
+# Notes: The order of actions is kept
+#
+# Since: 1.1
+##
+"#;
+    let comments = match comment_block(input.as_bytes()) {
+        nom::IResult::Done(_, c) => c,
+        other => panic!("Failed to parse comments: {:?}", other),
+    };
+    let description = Description::parse(&comments);
+    assert_eq!(
+        description.errors,
+        vec![
+            "Any errors from commands in the transaction.  If one fails, none of them are applied",
+            "If @actions is empty, GenericError",
+        ]
+    );
+    assert_eq!(
+        description.example,
+        Some(vec![
+            "-> { \"execute\": \"transaction\", \"arguments\": { \"actions\": [] } }".to_string(),
+            "<- { \"error\": {} }".to_string(),
+        ])
+    );
+    assert_eq!(description.version_since, Some(QemuVersion::new(1, 1, 0)));
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub struct Description {
     pub name: String,
+    /// The free text describing the definition, one line per entry with
+    /// empty lines between paragraphs.  Lines keep their indentation.
+    pub body: Vec<String>,
     pub parameters: Option<Vec<(String, String)>>,
     pub returns: Option<String>,
-    pub version_since: String,
+    /// The errors a command can fail with, one per case, from an Errors:
+    /// section or the "If ..." lines of older Returns: sections
+    pub errors: Vec<String>,
+    /// Example lines with their indentation
+    pub example: Option<Vec<String>>,
+    pub version_since: Option<QemuVersion>,
 }
 
-#[derive(Debug, PartialEq)]
-pub struct Struct {
-    pub name: String,
-    pub fields: Value,
-    pub base: Value,
+// The part of the doc block a line of text belongs to
+enum DocPart {
+    Body,
+    Parameter,
+    Returns,
+    Errors,
+    Example,
+    Other,
 }
 
-fn json_val_to_rust(input: &Value) -> String {
-    match input {
-        &Value::String(ref s) => match s.as_ref() {
-            "uint64" => "u64".to_string(),
-            "uint32" => "u32".to_string(),
-            "bool" => "bool".to_string(),
-            "int" => "f64".to_string(),
-            "str" => "String".to_string(),
-            _ => "String".to_string(),
-        },
-        &Value::Number(_) => "f64".to_string(),
-        &Value::Bool(_) => "bool".to_string(),
-        &Value::Null => "None".to_string(),
-        &Value::Object(_) => "struct".to_string(),
-        &Value::Array(_) => "Vec<String>".to_string(),
-        //&Value::Number(s) => "".to_string(),
+// A line that starts a section, like "Notes:" or "Since: 2.3"
+fn is_section_header(line: &str) -> bool {
+    match line.find(':') {
+        Some(i) => i > 0 && line[..i].chars().all(|c| c.is_alphabetic()),
+        None => false,
     }
 }
 
-impl Struct {
-    fn parse(input: &Value) -> Self {
-        // Check if base is first. Sometimes it comes first and sometimes data comes first
-        Struct {
-            name: input["struct"].as_str().unwrap().to_string(),
-            fields: input["data"].clone(),
-            base: input["base"].clone(),
+// Add a line to the last error case, or start one.  A case starts with
+// "If" or a "-" bullet.
+fn append_error(errors: &mut Vec<String>, line: &str) {
+    if let Some(case) = line.strip_prefix("- ") {
+        errors.push(case.trim().to_string());
+    } else if line.starts_with("If ") {
+        errors.push(line.to_string());
+    } else {
+        match errors.last_mut() {
+            Some(case) => {
+                case.push(' ');
+                case.push_str(line);
+            }
+            None => errors.push(line.to_string()),
         }
     }
-    pub fn to_rust_string(self) -> String {
-        let mut struct_fields: Vec<String> = Vec::new();
+}
 
-        if self.base.is_string() {
-            struct_fields.push(format!("base: {}", self.base.as_str().unwrap()));
+// Add a line to a section of running text
+fn append(text: &mut Option<String>, line: &str) {
+    match *text {
+        Some(ref mut t) if !t.is_empty() => {
+            t.push(' ');
+            t.push_str(line);
         }
+        _ => *text = Some(line.to_string()),
+    }
+}
 
-        if self.fields.is_object() {
-            for (key, value) in self.fields.as_object().unwrap() {
-                let name = sanitize_name(key);
-                struct_fields.push(format!("pub {name}:{type}",name=name,
-                    type=json_val_to_rust(value)
-                ));
+impl Description {
+    /// Parse the comment block in front of a definition
+    pub fn parse(comments: &[String]) -> Description {
+        let mut description = Description {
+            name: String::new(),
+            body: Vec::new(),
+            parameters: None,
+            returns: None,
+            errors: Vec::new(),
+            example: None,
+            version_since: None,
+        };
+        let mut parameters: Vec<(String, String)> = Vec::new();
+        let mut example: Vec<String> = Vec::new();
+        let mut part = DocPart::Body;
+
+        for raw in comments {
+            // The ## markers show up as a lone #
+            if raw.trim() == "#" {
+                continue;
+            }
+            let line = raw.trim();
+            let lower = line.to_lowercase();
+            if let DocPart::Example = part {
+                // Examples run until the next section
+                if !lower.starts_with("since") && !is_section_header(line) {
+                    if !line.is_empty() || !example.is_empty() {
+                        example.push(raw.strip_prefix(' ').unwrap_or(raw).trim_end().to_string());
+                    }
+                    continue;
+                }
+            }
+            if line.is_empty() {
+                if let DocPart::Body = part {
+                    if description.body.last().is_some_and(|l| !l.is_empty()) {
+                        description.body.push(String::new());
+                    }
+                }
+                part = DocPart::Body;
+                continue;
+            }
+
+            if let Some(member) = line.strip_prefix('@') {
+                let (name, text) = match member.find(':') {
+                    Some(i) => (&member[..i], member[i + 1..].trim()),
+                    None => (member, ""),
+                };
+                if description.name.is_empty() && parameters.is_empty() {
+                    description.name = name.trim().to_string();
+                    part = DocPart::Body;
+                } else {
+                    parameters.push((name.trim().to_string(), text.to_string()));
+                    part = DocPart::Parameter;
+                }
+            } else if lower.starts_with("since") && QemuVersion::find(&line[5..]).is_some() {
+                description.version_since = QemuVersion::find(&line[5..]);
+                part = DocPart::Other;
+            } else if lower.starts_with("returns:") {
+                description.returns = Some(line[8..].trim().to_string());
+                part = DocPart::Returns;
+            } else if lower.starts_with("errors:") {
+                if !line[7..].trim().is_empty() {
+                    append_error(&mut description.errors, line[7..].trim());
+                }
+                part = DocPart::Errors;
+            } else if lower.starts_with("example") && line.ends_with(':') {
+                part = DocPart::Example;
+            } else if line.ends_with(':') || lower.starts_with("note") {
+                part = DocPart::Other;
+            } else {
+                match part {
+                    DocPart::Parameter => {
+                        let text = &mut parameters.last_mut().unwrap().1;
+                        text.push(' ');
+                        text.push_str(line);
+                    }
+                    DocPart::Returns if line.starts_with("If ") => {
+                        append_error(&mut description.errors, line);
+                        part = DocPart::Errors;
+                    }
+                    DocPart::Returns => append(&mut description.returns, line),
+                    DocPart::Errors => append_error(&mut description.errors, line),
+                    DocPart::Body => description
+                        .body
+                        .push(raw.strip_prefix(' ').unwrap_or(raw).trim_end().to_string()),
+                    DocPart::Example | DocPart::Other => {}
+                }
             }
         }
+        if description.body.last().is_some_and(|l| l.is_empty()) {
+            description.body.pop();
+        }
+        while example.last().is_some_and(|l| l.is_empty()) {
+            example.pop();
+        }
+        if !parameters.is_empty() {
+            description.parameters = Some(parameters);
+        }
+        if !example.is_empty() {
+            description.example = Some(example);
+        }
+        description
+    }
+}
 
-        format!(
-            r#"
-            #[derive(Debug, RustcDecodable)]
-            pub struct {name}{{
-                {fields}
-            }}
-            "#,
-            name = sanitize_name(&self.name),
-            fields = struct_fields.join(",")
-        )
+// The name of a definition, which has to be a string
+fn definition_name(input: &Value, kind: &str) -> Result<String, String> {
+    match input[kind] {
+        Value::String(ref name) => Ok(name.clone()),
+        ref other => Err(format!("The name of {} {} is not a string", kind, other)),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Struct {
+    pub name: String,
+    pub fields: Value,
+    pub base: Value,
+    pub condition: Value,
+    pub features: Value,
+}
+
+impl Struct {
+    fn parse(input: &Value) -> Result<Self, String> {
+        // Check if base is first. Sometimes it comes first and sometimes data comes first
+        Ok(Struct {
+            name: definition_name(input, "struct")?,
+            fields: input["data"].clone(),
+            base: input["base"].clone(),
+            condition: input["if"].clone(),
+            features: input["features"].clone(),
+        })
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Command {
     pub name: String,
     pub fields: Value,
     pub gen: Value,
     pub returns: Value,
+    pub allow_oob: Value,
+    pub success_response: Value,
+    pub condition: Value,
+    pub features: Value,
 }
 
 impl Command {
-    fn parse(input: &Value) -> Self {
-        Command {
-            name: input["command"].as_str().unwrap().to_string(),
+    fn parse(input: &Value) -> Result<Self, String> {
+        Ok(Command {
+            name: definition_name(input, "command")?,
             gen: input["gen"].clone(),
             fields: input["data"].clone(),
             returns: input["returns"].clone(),
-        }
-    }
-    // TODO Put this in a mod of just qemu commands
-    pub fn to_rust_string(self) -> String {
-        let mut struct_fields: Vec<String> = Vec::new();
-        let mut impl_fields: Vec<String> = Vec::new();
-        let mut impl_input: Vec<String> = Vec::new();
-        let mut returns = String::new();
-        let mut to_json: Vec<String> = Vec::new();
-
-        if self.fields.is_object() {
-            for (key, value) in self.fields.as_object().unwrap() {
-                let name = sanitize_name(key);
-                let field_type = json_val_to_rust(value);
-
-                to_json.push(format!(
-                    "to_json[\"arguments\"][\"{qemu_name}\"] = self.{name}.clone().into();",
-                    qemu_name = key.replace("*", ""),
-                    name = name
-                ));
-                struct_fields.push(format!("pub {name}:{type}", name=name, type=field_type));
-                impl_fields.push(format!("{name}:{name}", name = name));
-                impl_input.push(format!("{name}:{type}",name=name, type=field_type));
-            }
-        }
-
-        if !self.gen.is_null() {
-            struct_fields.push("gen: bool".to_string());
-            impl_input.push("gen: bool".to_string());
-            impl_fields.push("gen: gen".to_string());
-        }
-
-        if !self.returns.is_null() {
-            // This goes in the parse_qemu_response function
-            match self.returns {
-                Value::String(s) => {
-                    let name = sanitize_name(&s);
-                    returns.push_str(&format!(
-                        r#"
-                        fn parse_qemu_response(&self, response: &String) ->
-                        rustc_json::DecodeResult<{name}>{{
-                        rustc_json::decode(&response)
-                        }}
-                        "#,
-                        name = name
-                    ));
-                }
-                Value::Array(array) => {
-                    let name = array.clone().pop();
-                    match name {
-                        Some(n) => {
-                            returns.push_str(&format!(
-                                r#"
-                            fn parse_qemu_response(&self, response: &String) ->
-                            rustc_json::DecodeResult<{name}>{{
-                            rustc_json::decode(&response)
-                            }}
-                            "#,
-                                name = n
-                            ));
-                        }
-                        None => {
-                            // TODO: What should we do here if the array doesn't have a value?
-                        }
-                    }
-                }
-                _ => {}
-            };
-        } else {
-            let name = sanitize_name(&self.name);
-            returns.push_str(&format!(
-                r#"
-                fn parse_qemu_response(&self, response: &String) ->
-                rustc_json::DecodeResult<{name}>{{
-                rustc_json::decode(&response)
-                }}
-                "#,
-                name = name
-            ));
-        }
-
-        format!(
-            r#"
-        #[derive(Debug)]
-        pub struct {name} {{
-            {fields}
-        }}
-        impl {name} {{
-            pub fn new({impl_input})->{name}{{
-                {name}{{
-                    {impl_fields}
-                }}
-            }}
-        }}
-        impl QemuCmd for {name} {{
-            fn to_json(&self)->String{{
-                let mut to_json = json::Value::new_object();
-                to_json["execute"] = "{execute_name}".into();
-                {to_json_fields}
-                to_json.dump()
-            }}
-        }}
-        "#,
-            name = sanitize_name(&self.name),
-            execute_name = &self.name,
-            fields = struct_fields.join(","),
-            impl_fields = impl_fields.join(","),
-            impl_input = impl_input.join(","),
-            to_json_fields = to_json.join("\n") //,
-                                                //parse_response=returns
-        )
+            allow_oob: input["allow-oob"].clone(),
+            success_response: input["success-response"].clone(),
+            condition: input["if"].clone(),
+            features: input["features"].clone(),
+        })
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Union {
     pub name: String,
+    pub base: Value,
     pub discriminator: Value,
     pub data: Value,
+    pub condition: Value,
+    pub features: Value,
 }
 
 impl Union {
-    fn parse(input: &Value) -> Self {
-        Union {
-            name: input["union"].as_str().unwrap().to_string(),
+    fn parse(input: &Value) -> Result<Self, String> {
+        Ok(Union {
+            name: definition_name(input, "union")?,
+            base: input["base"].clone(),
             discriminator: input["discriminator"].clone(),
             data: input["data"].clone(),
-        }
+            condition: input["if"].clone(),
+            features: input["features"].clone(),
+        })
     }
-    pub fn to_rust_string(self) -> String {
-        let mut struct_fields: Vec<String> = Vec::new();
-
-        if self.data.is_object() {
-            for (key, value) in self.data.as_object().unwrap() {
-                //for f in self.data.entries() {
-                if key == "type" {
-                    struct_fields.push(format!("pub qemu_type:{type}",
-                        type=json_val_to_rust(value)
-                    ));
-                } else {
-                    struct_fields.push(format!("pub {name}:{type}",
-                        name=sanitize_name(key),
-                        type=json_val_to_rust(value)
-                    ));
-                }
-            }
-        }
+}
 
-        format!(
-            r#"
-            #[derive(Debug,RustcDecodable)]
-            pub struct {name}{{
-                {fields}
-            }}
-            "#,
-            name = self.name,
-            fields = struct_fields.join(",")
-        )
+#[derive(Clone, Debug, PartialEq)]
+pub struct Alternate {
+    pub name: String,
+    pub data: Value,
+    pub condition: Value,
+    pub features: Value,
+}
+
+impl Alternate {
+    fn parse(input: &Value) -> Result<Self, String> {
+        Ok(Alternate {
+            name: definition_name(input, "alternate")?,
+            data: input["data"].clone(),
+            condition: input["if"].clone(),
+            features: input["features"].clone(),
+        })
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Event {
     pub name: String,
     pub data: Value,
+    pub condition: Value,
+    pub features: Value,
 }
 
 impl Event {
-    fn parse(input: &Value) -> Self {
-        Event {
-            name: input["event"].as_str().unwrap().to_string(),
+    fn parse(input: &Value) -> Result<Self, String> {
+        Ok(Event {
+            name: definition_name(input, "event")?,
             data: input["data"].clone(),
-        }
-    }
-    pub fn to_rust_string(self) -> String {
-        let mut struct_fields: Vec<String> = Vec::new();
-
-        if self.data.is_object() {
-            for (key, value) in self.data.as_object().unwrap() {
-                let name = sanitize_name(key);
-                let field_type = json_val_to_rust(value);
-
-                struct_fields.push(format!("pub {name}:{type}", name=name, type=field_type));
-            }
-        }
-
-        format!(
-            r#"
-            #[derive(Debug)]
-            pub struct {name} {{
-                {fields}
-            }}
-            "#,
-            name = sanitize_name(&self.name),
-            fields = struct_fields.join(","),
-        )
+            condition: input["if"].clone(),
+            features: input["features"].clone(),
+        })
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Enum {
     pub name: String,
     pub fields: Value,
+    pub condition: Value,
+    pub features: Value,
 }
 
 impl Enum {
-    fn parse(input: &Value) -> Self {
-        Enum {
-            name: input["enum"].as_str().unwrap().to_string(),
+    fn parse(input: &Value) -> Result<Self, String> {
+        Ok(Enum {
+            name: definition_name(input, "enum")?,
             fields: input["data"].clone(),
-        }
-    }
-    pub fn to_rust_string(self) -> String {
-        let mut struct_fields: Vec<String> = Vec::new();
-
-        if self.fields.is_array() {
-            for f in self.fields.as_array().unwrap() {
-                match f {
-                    &Value::String(ref s) => {
-                        let name = sanitize_name(s);
-                        struct_fields.push(format!("{name}", name = name));
-                    }
-                    _ => {}
-                }
-            }
-        }
-
-        format!(
-            r#"
-            #[derive(Debug,RustcDecodable)]
-            pub enum {name} {{
-                {fields}
-            }}
-            "#,
-            name = sanitize_name(&self.name),
-            fields = struct_fields.join(","),
-        )
+            condition: input["if"].clone(),
+            features: input["features"].clone(),
+        })
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum QemuType {
     Struct(Struct),
     Command(Command),
@@ -521,70 +601,130 @@ pub enum QemuType {
     Include { name: String },
     Event(Event),
     Union(Union),
+    Alternate(Alternate),
     Unknown,
 }
 
 impl QemuType {
-    fn parse(input: Value) -> Self {
-        if !input["include"].is_null() {
+    fn parse(input: Value) -> Result<Self, String> {
+        Ok(if !input["include"].is_null() {
             QemuType::Include {
-                name: input["input"].as_str().unwrap_or("").to_string(),
+                name: definition_name(&input, "include")?,
             }
         } else if !input["enum"].is_null() {
-            QemuType::Enum(Enum::parse(&input))
+            QemuType::Enum(Enum::parse(&input)?)
         } else if !input["command"].is_null() {
-            QemuType::Command(Command::parse(&input))
+            QemuType::Command(Command::parse(&input)?)
         } else if !input["union"].is_null() {
-            QemuType::Union(Union::parse(&input))
+            QemuType::Union(Union::parse(&input)?)
         } else if !input["struct"].is_null() {
-            QemuType::Struct(Struct::parse(&input))
+            QemuType::Struct(Struct::parse(&input)?)
         } else if !input["event"].is_null() {
-            QemuType::Event(Event::parse(&input))
+            QemuType::Event(Event::parse(&input)?)
+        } else if !input["alternate"].is_null() {
+            QemuType::Alternate(Alternate::parse(&input)?)
         } else {
             QemuType::Unknown
+        })
+    }
+
+    /// The name this definition is referred to by, if it defines anything
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            QemuType::Struct(s) => Some(&s.name),
+            QemuType::Command(c) => Some(&c.name),
+            QemuType::Enum(e) => Some(&e.name),
+            QemuType::Event(e) => Some(&e.name),
+            QemuType::Union(u) => Some(&u.name),
+            QemuType::Alternate(a) => Some(&a.name),
+            &QemuType::Include { .. } | &QemuType::Unknown => None,
+        }
+    }
+
+    /// The QAPI keyword this definition was declared with
+    pub fn kind(&self) -> &'static str {
+        match *self {
+            QemuType::Struct(_) => "struct",
+            QemuType::Command(_) => "command",
+            QemuType::Enum(_) => "enum",
+            QemuType::Event(_) => "event",
+            QemuType::Union(_) => "union",
+            QemuType::Alternate(_) => "alternate",
+            QemuType::Include { .. } => "include",
+            QemuType::Unknown => "unknown",
         }
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Section {
     pub description: Vec<String>,
     pub qemu_type: QemuType,
+    /// The file this section came from, when loaded with `Schema::load`
+    pub module: Option<String>,
 }
 
 impl Section {
-    fn parse(input: &[u8]) -> nom::IResult<&[u8], Self> {
+    /// The parsed documentation of this section
+    pub fn doc(&self) -> Description {
+        Description::parse(&self.description)
+    }
+
+    // A definition and the comments in front of it, still as text
+    fn parse(input: &[u8]) -> nom::IResult<&[u8], (Vec<String>, String)> {
         // println!("Section parse input: {:?}", String::from_utf8_lossy(input));
-        chain!(
+        do_parse!(
             input,
-            comments: comment_block~
-            element: call!(find_element) ~
-            blanks?,
-            ||{
-                let result = if element.contains("#"){
-                    let clean_element = remove_comments(element);
-                    serde_json::from_str(&clean_element)
-                }else{
-                    serde_json::from_str(&element)
-                };
-                println!("Json result: {:?}", result);
-
-                Section{
-                    description: comments,
-                    qemu_type: QemuType::parse(result.unwrap()),
-                }
-            }
+            opt!(blanks) >>
+            comments: comment_block >>
+            element: call!(find_element) >>
+            opt!(blanks) >>
+            ((comments, element))
         )
     }
+
+    fn from_text(comments: Vec<String>, element: String) -> Result<Section, String> {
+        let result = if element.contains('#') {
+            serde_json::from_str(&remove_comments(element.clone()))
+        } else {
+            serde_json::from_str(&element)
+        };
+        let value = result.map_err(|e| {
+            let start = element.trim().lines().next().unwrap_or("");
+            format!("Invalid definition {}: {}", start, e)
+        })?;
+        Ok(Section {
+            description: comments,
+            qemu_type: QemuType::parse(value)?,
+            module: None,
+        })
+    }
 }
 
-pub fn parse_sections(input: &[u8]) -> nom::IResult<&[u8], Vec<Section>> {
-    chain!(
+// The sections of a schema.  Comments after the last definition don't
+// make a section.
+fn sections_from_text(input: &[u8]) -> nom::IResult<&[u8], Result<Vec<Section>, String>> {
+    do_parse!(
         input,
-        comment_block ~ //Get rid of the Mode: Python crap at the top
-        sections: many0!(call!(Section::parse)),
-        ||{
-            sections
-        }
+        comment_block >> //Get rid of the Mode: Python crap at the top
+        sections: many0!(call!(Section::parse)) >>
+        (sections
+            .into_iter()
+            .filter(|s| !s.1.trim().is_empty())
+            .map(|(comments, element)| Section::from_text(comments, element))
+            .collect())
     )
 }
+
+/// Split a schema into sections.  A definition that isn't valid fails the
+/// whole parse; `Schema::parse` tells what is wrong with it.
+pub fn parse_sections(input: &[u8]) -> nom::IResult<&[u8], Vec<Section>> {
+    match sections_from_text(input) {
+        nom::IResult::Done(rest, Ok(sections)) => nom::IResult::Done(rest, sections),
+        nom::IResult::Done(_, Err(_)) => {
+            nom::IResult::Error(error_position!(nom::ErrorKind::Custom(0), input))
+        }
+        nom::IResult::Incomplete(needed) => nom::IResult::Incomplete(needed),
+        nom::IResult::Error(e) => nom::IResult::Error(e),
+    }
+}
@@ -0,0 +1,373 @@
+// Turns the markup of QAPI doc comments into Markdown rustdoc renders the
+// way it was meant.  Markdown takes anything indented by four spaces for a
+// code block, which rustdoc would then run as a doctest, so only list items
+// keep their indentation and everything literal goes in fenced blocks.
+
+/// What an @name reference in the docs stands for
+pub enum Reference {
+    /// The path of a generated item, for an intra-doc link
+    Path(String),
+    /// Something that exists but has no generated item to link to
+    Code,
+    /// Nothing by that name
+    Broken,
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_' || c == '.'
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+// The length of the marker of a list item, with its space
+fn list_marker(line: &str) -> Option<usize> {
+    if line.starts_with("- ") || line.starts_with("* ") || line.starts_with("+ ") {
+        return Some(2);
+    }
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 && (line[digits..].starts_with(". ") || line[digits..].starts_with(") ")) {
+        return Some(digits + 2);
+    }
+    None
+}
+
+// Lines without their common indentation or blank lines around them
+fn dedent(lines: &[&str]) -> Vec<String> {
+    let mut lines = lines.to_vec();
+    while lines.last().is_some_and(|l| l.trim().is_empty()) {
+        lines.pop();
+    }
+    let blank = lines.iter().take_while(|l| l.trim().is_empty()).count();
+    lines.drain(..blank);
+    let indent = lines
+        .iter()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| indentation(l))
+        .min()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .map(|l| l.get(indent..).unwrap_or("").trim_end().to_string())
+        .collect()
+}
+
+// A fenced block, set off from what comes before it
+fn fence(out: &mut Vec<String>, language: &str, lines: &[&str]) {
+    if out.last().is_some_and(|l| !l.is_empty()) {
+        out.push(String::new());
+    }
+    out.push(format!("```{}", language));
+    out.extend(dedent(lines));
+    out.push("```".to_string());
+}
+
+/// Markup within a line: @references, *strong* and _emphasis_.  Brackets
+/// and angle brackets are escaped so rustdoc doesn't take them for links
+/// or HTML.  Names that resolve to nothing are added to broken.
+pub fn inline(text: &str, resolve: &dyn Fn(&str) -> Reference, broken: &mut Vec<String>) -> String {
+    // Old schemas mark optional members in their description, sometimes
+    // with punctuation after the marker
+    let text = match text.trim_start().strip_prefix("#optional") {
+        Some(rest) => rest.trim_start_matches(|c: char| c == ',' || c == ':' || c.is_whitespace()),
+        None => text.trim_start(),
+    };
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let after_word = i > 0 && chars[i - 1].is_alphanumeric();
+        let before_word = chars.get(i + 1).is_some_and(|c| !c.is_whitespace());
+        match c {
+            // Code spans stay as they are
+            '`' => match chars[i + 1..].iter().position(|&d| d == '`') {
+                Some(n) => {
+                    out.extend(&chars[i..i + n + 2]);
+                    i += n + 2;
+                    continue;
+                }
+                None => out.push_str("\\`"),
+            },
+            '@' if !after_word && before_word => {
+                let len = chars[i + 1..]
+                    .iter()
+                    .take_while(|&&c| is_name_char(c))
+                    .count();
+                let mut name: String = chars[i + 1..i + 1 + len].iter().collect();
+                // The name may end a sentence
+                while name.ends_with('.') {
+                    name.pop();
+                }
+                // A lone @ is just punctuation
+                if name.is_empty() {
+                    out.push(c);
+                    i += 1;
+                    continue;
+                }
+                match resolve(&name) {
+                    Reference::Path(ref path) if *path == name => {
+                        out.push_str(&format!("[`{}`]", name))
+                    }
+                    Reference::Path(path) => out.push_str(&format!("[`{}`]({})", name, path)),
+                    Reference::Code => out.push_str(&format!("`{}`", name)),
+                    Reference::Broken => {
+                        out.push_str(&format!("`{}`", name));
+                        broken.push(name.clone());
+                    }
+                }
+                i += 1 + name.chars().count();
+                continue;
+            }
+            '*' | '_' if !after_word && before_word => {
+                let close = (i + 2..chars.len()).find(|&j| {
+                    chars[j] == c
+                        && !chars[j - 1].is_whitespace()
+                        && !chars.get(j + 1).is_some_and(|d| d.is_alphanumeric())
+                });
+                match close {
+                    Some(j) => {
+                        let inner: String = chars[i + 1..j].iter().collect();
+                        let marker = if c == '*' { "**" } else { "*" };
+                        out.push_str(marker);
+                        out.push_str(&inline(&inner, resolve, broken));
+                        out.push_str(marker);
+                        i = j + 1;
+                        continue;
+                    }
+                    None => {
+                        out.push('\\');
+                        out.push(c);
+                    }
+                }
+            }
+            '*' | '[' | ']' | '<' => {
+                out.push('\\');
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+        i += 1;
+    }
+    out
+}
+
+/// Running text: paragraphs, indented lists, and literal blocks introduced
+/// by a line ending in "::" as in reStructuredText
+pub fn text(
+    lines: &[String],
+    resolve: &dyn Fn(&str) -> Reference,
+    broken: &mut Vec<String>,
+) -> Vec<String> {
+    let mut out = Vec::new();
+    // The indentation of each open list item in the input, and that of its
+    // content in the output
+    let mut items: Vec<(usize, usize)> = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i].trim_end();
+        let trimmed = line.trim_start();
+        let indent = indentation(line);
+        i += 1;
+        if trimmed.is_empty() {
+            if out.last().is_some_and(|l: &String| !l.is_empty()) {
+                out.push(String::new());
+            }
+            continue;
+        }
+
+        // Lines go with the innermost list item they are indented past
+        while items.last().is_some_and(|&(item, _)| item >= indent) {
+            items.pop();
+        }
+        let prefix = items.last().map_or(0, |&(_, content)| content);
+        let converted = match list_marker(trimmed) {
+            Some(marker) => {
+                items.push((indent, prefix + marker));
+                format!(
+                    "{}{}{}",
+                    " ".repeat(prefix),
+                    &trimmed[..marker],
+                    inline(&trimmed[marker..], resolve, broken)
+                )
+            }
+            None => format!("{}{}", " ".repeat(prefix), inline(trimmed, resolve, broken)),
+        };
+        if !trimmed.ends_with("::") {
+            out.push(converted);
+            continue;
+        }
+
+        // "text::" becomes "text:", and a lone "::" goes away
+        if trimmed != "::" {
+            out.push(converted[..converted.len() - 1].to_string());
+        }
+        let start = i;
+        while i < lines.len() && (lines[i].trim().is_empty() || indentation(&lines[i]) > indent) {
+            i += 1;
+        }
+        let block: Vec<&str> = lines[start..i].iter().map(|l| l.as_str()).collect();
+        if block.iter().any(|l| !l.trim().is_empty()) {
+            fence(&mut out, "text", &block);
+            out.push(String::new());
+            items.clear();
+        }
+    }
+    while out.last().is_some_and(|l| l.is_empty()) {
+        out.pop();
+    }
+    out
+}
+
+fn is_message(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with("->") || line.starts_with("<-")
+}
+
+/// An example.  Its "->" and "<-" transcripts of QMP messages go in json
+/// blocks, and whatever text is between them is converted like any other.
+pub fn example(
+    lines: &[String],
+    resolve: &dyn Fn(&str) -> Reference,
+    broken: &mut Vec<String>,
+) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let start = i;
+        if !is_message(&lines[i]) {
+            while i < lines.len() && !is_message(&lines[i]) {
+                i += 1;
+            }
+            out.extend(text(&lines[start..i], resolve, broken));
+            continue;
+        }
+        // A message runs on over the lines indented past its arrow
+        let mut indent = indentation(&lines[i]);
+        while i < lines.len() {
+            let line = &lines[i];
+            if is_message(line) {
+                indent = indentation(line);
+            } else if !line.trim().is_empty() && indentation(line) <= indent {
+                break;
+            }
+            i += 1;
+        }
+        let block: Vec<&str> = lines[start..i].iter().map(|l| l.as_str()).collect();
+        fence(&mut out, "json", &block);
+        if i < lines.len() {
+            out.push(String::new());
+        }
+    }
+    out
+}
+
+#[test]
+fn test_inline() {
+    let resolve = |name: &str| match name {
+        "device" => Reference::Path("Eject::device".to_string()),
+        "str" => Reference::Code,
+        _ => Reference::Broken,
+    };
+    let mut broken = Vec::new();
+    assert_eq!(
+        inline(
+            "#optional the @device, a @str. See @nowhere.",
+            &resolve,
+            &mut broken
+        ),
+        "the [`device`](Eject::device), a `str`. See `nowhere`."
+    );
+    assert_eq!(broken, vec!["nowhere"]);
+    assert_eq!(
+        inline(
+            "*must* be _one_ of [a, b] for block_passwd, not <x>",
+            &resolve,
+            &mut broken
+        ),
+        "**must** be *one* of \\[a, b\\] for block_passwd, not \\<x>"
+    );
+    assert_eq!(
+        inline("mail qemu@nongnu.org, `@raw`", &resolve, &mut broken),
+        "mail qemu@nongnu.org, `@raw`"
+    );
+    assert_eq!(broken.len(), 1);
+    assert_eq!(
+        inline(
+            "#optional, if the corruption resulted from an image access (@device)",
+            &resolve,
+            &mut broken
+        ),
+        "if the corruption resulted from an image access ([`device`](Eject::device))"
+    );
+    assert_eq!(inline("#optional: a @str", &resolve, &mut broken), "a `str`");
+    assert_eq!(inline("mark (@) or @. here", &resolve, &mut broken), "mark (@) or @. here");
+    assert_eq!(broken.len(), 1);
+}
+
+#[test]
+fn test_text() {
+    let resolve = |_: &str| Reference::Broken;
+    let mut broken = Vec::new();
+    let lines: Vec<String> = vec![
+        "The modes are:",
+        "",
+        "    - full: everything",
+        "      that is there",
+        "        - nested",
+        "    - top: only the top",
+        "",
+        "For instance::",
+        "",
+        "    qemu-img info disk.qcow2",
+        "",
+        "Done.",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect();
+    let expected = vec![
+        "The modes are:",
+        "",
+        "- full: everything",
+        "  that is there",
+        "  - nested",
+        "- top: only the top",
+        "",
+        "For instance:",
+        "",
+        "```text",
+        "qemu-img info disk.qcow2",
+        "```",
+        "",
+        "Done.",
+    ];
+    assert_eq!(text(&lines, &resolve, &mut broken), expected);
+}
+
+#[test]
+fn test_example() {
+    let resolve = |_: &str| Reference::Broken;
+    let mut broken = Vec::new();
+    let lines: Vec<String> = vec![
+        "1. Eject the tray",
+        "",
+        "-> { \"execute\": \"eject\",",
+        "     \"arguments\": { \"device\": \"ide1-cd0\" } }",
+        "<- { \"return\": {} }",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect();
+    let expected = vec![
+        "1. Eject the tray",
+        "",
+        "```json",
+        "-> { \"execute\": \"eject\",",
+        "     \"arguments\": { \"device\": \"ide1-cd0\" } }",
+        "<- { \"return\": {} }",
+        "```",
+    ];
+    assert_eq!(example(&lines, &resolve, &mut broken), expected);
+}
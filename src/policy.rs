@@ -0,0 +1,272 @@
+extern crate serde_json;
+
+use self::serde_json::{Map, Value};
+
+use graph::DependencyGraph;
+use schema::{names, Schema};
+use QemuType;
+
+/// What to do with deprecated or unstable input, like QEMU's
+/// `-compat deprecated-input=...`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum InputPolicy {
+    Accept,
+    Reject,
+    Crash,
+}
+
+/// What to do with deprecated or unstable output, like QEMU's
+/// `-compat deprecated-output=...`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OutputPolicy {
+    Accept,
+    Hide,
+}
+
+/// The policy QEMU applies to the 'deprecated' and 'unstable' features
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CompatPolicy {
+    pub deprecated_input: InputPolicy,
+    pub deprecated_output: OutputPolicy,
+    pub unstable_input: InputPolicy,
+    pub unstable_output: OutputPolicy,
+}
+
+impl Default for CompatPolicy {
+    fn default() -> Self {
+        CompatPolicy {
+            deprecated_input: InputPolicy::Accept,
+            deprecated_output: OutputPolicy::Accept,
+            unstable_input: InputPolicy::Accept,
+            unstable_output: OutputPolicy::Accept,
+        }
+    }
+}
+
+fn parse_input(value: &str) -> Result<InputPolicy, String> {
+    match value {
+        "accept" => Ok(InputPolicy::Accept),
+        "reject" => Ok(InputPolicy::Reject),
+        "crash" => Ok(InputPolicy::Crash),
+        _ => Err(format!("Unknown input policy: {}", value)),
+    }
+}
+
+fn parse_output(value: &str) -> Result<OutputPolicy, String> {
+    match value {
+        "accept" => Ok(OutputPolicy::Accept),
+        "hide" => Ok(OutputPolicy::Hide),
+        _ => Err(format!("Unknown output policy: {}", value)),
+    }
+}
+
+impl CompatPolicy {
+    /// Parse the argument of QEMU's -compat option, for example
+    /// "deprecated-input=reject,deprecated-output=hide"
+    pub fn parse(input: &str) -> Result<CompatPolicy, String> {
+        let mut policy = CompatPolicy::default();
+        for option in input.split(',').filter(|o| !o.is_empty()) {
+            let mut parts = option.splitn(2, '=');
+            let key = parts.next().unwrap();
+            let value = match parts.next() {
+                Some(v) => v,
+                None => return Err(format!("Missing value for {}", key)),
+            };
+            match key {
+                "deprecated-input" => policy.deprecated_input = parse_input(value)?,
+                "deprecated-output" => policy.deprecated_output = parse_output(value)?,
+                "unstable-input" => policy.unstable_input = parse_input(value)?,
+                "unstable-output" => policy.unstable_output = parse_output(value)?,
+                _ => return Err(format!("Unknown compat option: {}", key)),
+            }
+        }
+        Ok(policy)
+    }
+
+    fn drops_input(&self, features: &Value) -> bool {
+        let features = names(features);
+        (features.contains(&"deprecated") && self.deprecated_input != InputPolicy::Accept)
+            || (features.contains(&"unstable") && self.unstable_input != InputPolicy::Accept)
+    }
+
+    fn drops_output(&self, features: &Value) -> bool {
+        let features = names(features);
+        (features.contains(&"deprecated") && self.deprecated_output == OutputPolicy::Hide)
+            || (features.contains(&"unstable") && self.unstable_output == OutputPolicy::Hide)
+    }
+}
+
+// Which way a type travels over the wire
+#[derive(Clone, Copy)]
+struct Direction {
+    input: bool,
+    output: bool,
+}
+
+impl Direction {
+    // Only drop what can't show up in any direction the type is used in.
+    // Types nobody uses are left alone.
+    fn drops(&self, policy: &CompatPolicy, features: &Value) -> bool {
+        (self.input || self.output)
+            && (!self.input || policy.drops_input(features))
+            && (!self.output || policy.drops_output(features))
+    }
+}
+
+fn member_features(member: &Value) -> &Value {
+    match member {
+        Value::Object(o) => o.get("features").unwrap_or(&Value::Null),
+        _ => &Value::Null,
+    }
+}
+
+fn filter_members(input: &Value, direction: Direction, policy: &CompatPolicy) -> Value {
+    match input {
+        Value::Object(o) => {
+            let mut members = Map::new();
+            for (name, member) in o {
+                if !direction.drops(policy, member_features(member)) {
+                    members.insert(name.clone(), member.clone());
+                }
+            }
+            Value::Object(members)
+        }
+        Value::Array(a) => Value::Array(
+            a.iter()
+                .filter(|v| !direction.drops(policy, member_features(v)))
+                .cloned()
+                .collect(),
+        ),
+        _ => input.clone(),
+    }
+}
+
+impl Schema {
+    /// The schema as seen by a client of a QEMU running with the given
+    /// -compat policy.  Commands and arguments that would be rejected are
+    /// removed, as are events, members, branches and enum values that would
+    /// be hidden.
+    /// Whatever stays keeps its features so it can still be marked.
+    pub fn apply_policy(&self, policy: &CompatPolicy) -> Schema {
+        let graph = DependencyGraph::new(self);
+        let input = graph.input_types();
+        let output = graph.output_types();
+        let direction = |name: &str| Direction {
+            input: input.contains(name),
+            output: output.contains(name),
+        };
+
+        let mut sections = Vec::new();
+        for section in &self.sections {
+            let mut section = section.clone();
+            match section.qemu_type {
+                QemuType::Command(ref mut c) => {
+                    if policy.drops_input(&c.features) {
+                        continue;
+                    }
+                    let args = Direction {
+                        input: true,
+                        output: false,
+                    };
+                    c.fields = filter_members(&c.fields, args, policy);
+                }
+                QemuType::Event(ref mut e) => {
+                    if policy.drops_output(&e.features) {
+                        continue;
+                    }
+                    let data = Direction {
+                        input: false,
+                        output: true,
+                    };
+                    e.data = filter_members(&e.data, data, policy);
+                }
+                QemuType::Struct(ref mut s) => {
+                    s.fields = filter_members(&s.fields, direction(&s.name), policy);
+                }
+                QemuType::Union(ref mut u) => {
+                    u.base = filter_members(&u.base, direction(&u.name), policy);
+                    u.data = filter_members(&u.data, direction(&u.name), policy);
+                }
+                QemuType::Alternate(ref mut a) => {
+                    a.data = filter_members(&a.data, direction(&a.name), policy);
+                }
+                QemuType::Enum(ref mut e) => {
+                    e.fields = filter_members(&e.fields, direction(&e.name), policy);
+                }
+                _ => {}
+            }
+            sections.push(section);
+        }
+        Schema::from_sections(sections)
+    }
+}
+
+#[test]
+fn test_parse_policy() {
+    let policy =
+        CompatPolicy::parse("deprecated-input=reject,deprecated-output=hide,unstable-input=crash")
+            .unwrap();
+    assert_eq!(policy.deprecated_input, InputPolicy::Reject);
+    assert_eq!(policy.deprecated_output, OutputPolicy::Hide);
+    assert_eq!(policy.unstable_input, InputPolicy::Crash);
+    assert_eq!(policy.unstable_output, OutputPolicy::Accept);
+    assert!(CompatPolicy::parse("deprecated-input=hide").is_err());
+    assert!(CompatPolicy::parse("bogus=accept").is_err());
+}
+
+#[test]
+fn test_apply_policy() {
+    let input = r#"
+{ 'enum': 'MigrationCapability',
+  'data': [ 'xbzrle', { 'name': 'block', 'features': [ 'deprecated' ] },
+            { 'name': 'x-colo', 'features': [ 'unstable' ] } ] }
+{ 'struct': 'MigrationInfo',
+  'data': { 'status': 'str',
+            '*disk': { 'type': 'int', 'features': [ 'deprecated' ] } } }
+{ 'command': 'query-migrate', 'returns': 'MigrationInfo' }
+{ 'command': 'migrate-set-capabilities',
+  'data': { 'capabilities': [ 'MigrationCapability' ],
+            '*blk': { 'type': 'bool', 'features': [ 'deprecated' ] } } }
+{ 'command': 'migrate-set-cache-size', 'data': { 'value': 'int' },
+  'features': [ 'deprecated' ] }
+{ 'event': 'MIGRATION_PASS', 'data': { 'pass': 'int' },
+  'features': [ 'unstable' ] }
+{ 'union': 'MigrationTarget',
+  'data': { 'tcp': 'str', 'exec': { 'type': 'str', 'features': [ 'deprecated' ] } } }
+{ 'alternate': 'CacheSize',
+  'data': { 'bytes': 'int', 'percent': { 'type': 'number', 'features': [ 'deprecated' ] } } }
+{ 'command': 'migrate-to', 'data': { 'target': 'MigrationTarget', '*cache': 'CacheSize' } }
+"#;
+    let schema = Schema::parse(input).unwrap();
+    let policy =
+        CompatPolicy::parse("deprecated-input=reject,deprecated-output=hide,unstable-input=reject")
+            .unwrap();
+    let hardened = schema.apply_policy(&policy);
+
+    assert!(hardened.get("migrate-set-cache-size").is_none());
+    // Unstable output is still accepted
+    assert!(hardened.get("MIGRATION_PASS").is_some());
+    match hardened.get("migrate-set-capabilities") {
+        Some(QemuType::Command(c)) => assert!(c.fields.get("*blk").is_none()),
+        other => panic!("Expected a command: {:?}", other),
+    }
+    match hardened.get("MigrationInfo") {
+        Some(QemuType::Struct(s)) => assert!(s.fields.get("*disk").is_none()),
+        other => panic!("Expected a struct: {:?}", other),
+    }
+    match hardened.get("MigrationTarget") {
+        Some(QemuType::Union(u)) => assert!(u.data.get("exec").is_none()),
+        other => panic!("Expected a union: {:?}", other),
+    }
+    match hardened.get("CacheSize") {
+        Some(QemuType::Alternate(a)) => assert!(a.data.get("percent").is_none()),
+        other => panic!("Expected an alternate: {:?}", other),
+    }
+    match hardened.get("MigrationCapability") {
+        Some(QemuType::Enum(e)) => assert_eq!(names(&e.fields), vec!["xbzrle"]),
+        other => panic!("Expected an enum: {:?}", other),
+    }
+
+    // Nothing changes with the default policy
+    assert_eq!(schema.apply_policy(&CompatPolicy::default()), schema);
+}
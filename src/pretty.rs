@@ -0,0 +1,253 @@
+extern crate proc_macro2;
+
+use self::proc_macro2::{Delimiter, Group, Spacing, TokenStream, TokenTree};
+
+const INDENT: &str = "    ";
+
+// Lays out the token streams built by the code generator the way rustfmt
+// would.  It only needs to know about the items the generator emits:
+// attributes, structs, enums, traits and impls with simple bodies.
+struct Printer {
+    out: String,
+    indent: usize,
+    line_start: bool,
+}
+
+fn is_punct(token: Option<&TokenTree>, c: char) -> bool {
+    match token {
+        Some(TokenTree::Punct(p)) => p.as_char() == c,
+        _ => false,
+    }
+}
+
+fn is_ident(token: Option<&TokenTree>, name: &str) -> bool {
+    match token {
+        Some(TokenTree::Ident(i)) => i == name,
+        _ => false,
+    }
+}
+
+// The text of a string literal
+fn unescape(literal: &str) -> Option<String> {
+    if let Some(raw) = literal.strip_prefix('r') {
+        let hashes = raw.chars().take_while(|&c| c == '#').count();
+        let start = 2 + hashes;
+        return literal.get(start..literal.len().checked_sub(1 + hashes)?).map(|s| s.to_string());
+    }
+    let inner = literal.get(1..literal.len().checked_sub(1)?)?;
+    let mut text = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => text.push('\n'),
+            'r' => text.push('\r'),
+            't' => text.push('\t'),
+            '0' => text.push('\0'),
+            'u' => {
+                let code: String = chars.by_ref().skip(1).take_while(|&c| c != '}').collect();
+                text.push(::std::char::from_u32(u32::from_str_radix(&code, 16).ok()?)?);
+            }
+            c => text.push(c),
+        }
+    }
+    Some(text)
+}
+
+// The text of a #[doc = "..."] attribute
+fn doc(group: &Group) -> Option<String> {
+    let tokens: Vec<TokenTree> = group.stream().into_iter().collect();
+    match (tokens.first(), tokens.get(1), tokens.get(2), tokens.len()) {
+        (
+            Some(TokenTree::Ident(i)),
+            Some(TokenTree::Punct(p)),
+            Some(TokenTree::Literal(l)),
+            3,
+        ) if i == "doc" && p.as_char() == '=' => unescape(&l.to_string()),
+        _ => None,
+    }
+}
+
+impl Printer {
+    fn write(&mut self, text: &str) {
+        if self.line_start {
+            for _ in 0..self.indent {
+                self.out.push_str(INDENT);
+            }
+            self.line_start = false;
+        }
+        self.out.push_str(text);
+    }
+
+    fn newline(&mut self) {
+        if !self.line_start {
+            self.out.push('\n');
+            self.line_start = true;
+        }
+    }
+
+    fn blank_line(&mut self) {
+        self.newline();
+        if !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+
+    fn space_before(&self, next: &TokenTree) -> bool {
+        let last = match self.out.chars().last() {
+            Some(c) if !self.line_start => c,
+            _ => return false,
+        };
+        let path = self.out.ends_with("::");
+        let word = last.is_alphanumeric() || last == '_';
+        match next {
+            TokenTree::Punct(p) => match p.as_char() {
+                ',' | ';' | ':' | '.' | '!' | '?' | '<' | '>' => false,
+                '=' | '-' => true,
+                _ => !path && !"([<&'#!{".contains(last),
+            },
+            &TokenTree::Ident(_) | &TokenTree::Literal(_) => {
+                !path && !"([<&'#!.{".contains(last)
+            }
+            TokenTree::Group(g) => match g.delimiter() {
+                Delimiter::Parenthesis => !word && !"([<&!".contains(last),
+                Delimiter::Bracket => !word && !"#!([<&)]".contains(last),
+                Delimiter::Brace => !path && !"({[".contains(last),
+                Delimiter::None => true,
+            },
+        }
+    }
+
+    fn token(&mut self, token: &TokenTree) {
+        if self.space_before(token) {
+            self.write(" ");
+        }
+        match token {
+            TokenTree::Group(g) => self.inline_group(g),
+            TokenTree::Punct(p) => self.write(&p.as_char().to_string()),
+            t => self.write(&t.to_string()),
+        }
+    }
+
+    fn inline(&mut self, stream: TokenStream) {
+        for token in stream {
+            self.token(&token);
+        }
+    }
+
+    fn inline_group(&mut self, group: &Group) {
+        let (open, close) = match group.delimiter() {
+            Delimiter::Parenthesis => ("(", ")"),
+            Delimiter::Bracket => ("[", "]"),
+            Delimiter::Brace => ("{", "}"),
+            Delimiter::None => ("", ""),
+        };
+        self.write(open);
+        self.inline(group.stream());
+        self.write(close);
+    }
+
+    // A braced body gets its own indented lines
+    fn braces(&mut self, group: &Group) {
+        if !self.line_start {
+            self.write(" ");
+        }
+        if group.stream().is_empty() {
+            self.write("{}");
+            return;
+        }
+        self.write("{");
+        self.newline();
+        self.indent += 1;
+        self.block(group.stream(), false);
+        self.newline();
+        self.indent -= 1;
+        self.write("}");
+    }
+
+    // Items and fields go one per line.  At the top level items are
+    // separated by a blank line, except for runs of use declarations.
+    fn block(&mut self, stream: TokenStream, top: bool) {
+        let tokens: Vec<TokenTree> = stream.into_iter().collect();
+        // Commas inside generic arguments don't end a line
+        let mut angle = 0;
+        let mut i = 0;
+        while i < tokens.len() {
+            let next = tokens.get(i + 1);
+            let end = match tokens[i] {
+                TokenTree::Punct(ref p) if p.as_char() == '#' => {
+                    // Doc attributes go back to being doc comments
+                    if let Some(TokenTree::Group(g)) = next {
+                        if let Some(text) = doc(g) {
+                            for line in text.split('\n') {
+                                self.write(format!("///{}", line).trim_end());
+                                self.newline();
+                            }
+                            i += 2;
+                            continue;
+                        }
+                    }
+                    self.token(&tokens[i]);
+                    if let Some(TokenTree::Group(g)) = next {
+                        self.token(&tokens[i + 1]);
+                        if g.delimiter() == Delimiter::Bracket {
+                            self.newline();
+                        }
+                        i += 1;
+                    }
+                    false
+                }
+                TokenTree::Punct(ref p) => {
+                    let joined = i > 0
+                        && match tokens[i - 1] {
+                            TokenTree::Punct(ref q) => q.spacing() == Spacing::Joint,
+                            _ => false,
+                        };
+                    match p.as_char() {
+                        '<' => angle += 1,
+                        '>' if !joined => angle -= 1,
+                        _ => {}
+                    }
+                    self.token(&tokens[i]);
+                    p.as_char() == ';' || (p.as_char() == ',' && angle == 0)
+                }
+                TokenTree::Group(ref g)
+                    if g.delimiter() == Delimiter::Brace && !self.out.ends_with("::") =>
+                {
+                    self.braces(g);
+                    !is_punct(next, ',') && !is_punct(next, ';')
+                }
+                ref t => {
+                    self.token(t);
+                    false
+                }
+            };
+            if end {
+                if top && !(is_punct(tokens.get(i), ';') && is_ident(next, "use")) {
+                    self.blank_line();
+                } else {
+                    self.newline();
+                }
+            }
+            i += 1;
+        }
+    }
+}
+
+/// Render generated code as formatted source
+pub fn pretty(tokens: TokenStream) -> String {
+    let mut printer = Printer {
+        out: String::new(),
+        indent: 0,
+        line_start: true,
+    };
+    printer.block(tokens, true);
+    printer.newline();
+    while printer.out.ends_with("\n\n") {
+        printer.out.pop();
+    }
+    printer.out
+}
@@ -0,0 +1,61 @@
+use std::collections::BTreeSet;
+
+use graph::DependencyGraph;
+use schema::Schema;
+use QemuType;
+
+impl Schema {
+    /// A copy of the schema with only the given commands and events, plus
+    /// every type they use directly or through other types.  Includes are
+    /// kept so the module layout stays the same.
+    pub fn prune(&self, keep: &[&str]) -> Result<Schema, String> {
+        let graph = DependencyGraph::new(self);
+        let mut wanted: BTreeSet<String> = BTreeSet::new();
+        for name in keep {
+            if self.get(name).is_none() {
+                return Err(format!("{} is not defined in the schema", name));
+            }
+            wanted.insert(name.to_string());
+            wanted.extend(graph.reachable_from(name));
+        }
+
+        let sections = self
+            .sections
+            .iter()
+            .filter(|s| match s.qemu_type {
+                QemuType::Include { .. } => true,
+                _ => match s.qemu_type.name() {
+                    Some(name) => wanted.contains(name),
+                    None => false,
+                },
+            })
+            .cloned()
+            .collect();
+        Ok(Schema::from_sections(sections))
+    }
+}
+
+#[test]
+fn test_prune() {
+    let input = r#"
+{ 'enum': 'Color', 'data': [ 'red', 'green' ] }
+{ 'struct': 'Layer', 'data': { 'color': 'Color' } }
+{ 'struct': 'Paint', 'data': { '*layers': ['Layer'] } }
+{ 'struct': 'Brush', 'data': { 'size': 'int' } }
+{ 'command': 'query-paint', 'returns': ['Paint'] }
+{ 'command': 'query-brush', 'returns': 'Brush' }
+{ 'event': 'PAINT_DRY', 'data': { 'color': 'Color' } }
+"#;
+    let schema = Schema::parse(input).unwrap();
+    let pruned = schema.prune(&["query-paint", "PAINT_DRY"]).unwrap();
+    let names: Vec<&str> = pruned
+        .definitions()
+        .iter()
+        .map(|d| d.name().unwrap())
+        .collect();
+    assert_eq!(
+        names,
+        vec!["Color", "Layer", "Paint", "query-paint", "PAINT_DRY"]
+    );
+    assert!(schema.prune(&["query-nothing"]).is_err());
+}
@@ -0,0 +1,287 @@
+extern crate nom;
+extern crate serde_json;
+
+use self::serde_json::Value;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use graph::{DependencyGraph, DependencyKind};
+use {sections_from_text, QemuType, Section};
+
+// Types every QAPI schema gets for free.  References to these never point at
+// another definition.
+const BUILTIN_TYPES: &[&str] = &[
+    "str", "int", "int8", "int16", "int32", "int64", "uint8", "uint16", "uint32", "uint64", "size",
+    "number", "bool", "null", "any", "QType",
+];
+
+pub fn is_builtin(name: &str) -> bool {
+    BUILTIN_TYPES.contains(&name)
+}
+
+// Members can either be written as 'name': 'type' or in the longer
+// 'name': { 'type': 'type', ... } form.  Returns the type in both cases.
+pub fn member_type(value: &Value) -> &Value {
+    match value {
+        Value::Object(o) if o.contains_key("type") => &o["type"],
+        _ => value,
+    }
+}
+
+// Features are either plain names or { 'name': ..., 'if': ... }.  Enum
+// values are written the same way.
+pub fn names(list: &Value) -> Vec<&str> {
+    match list {
+        Value::Array(a) => a
+            .iter()
+            .filter_map(|f| match f {
+                Value::String(s) => Some(s.as_str()),
+                Value::Object(o) => o.get("name").and_then(|n| n.as_str()),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+// QAPI quotes strings with ' where JSON wants ".  A " inside a string has
+// to be escaped for JSON.  Quotes in comments are prose, like the
+// apostrophe in "the host's", and are left alone.
+fn double_quotes(input: &str) -> String {
+    let mut buf = String::with_capacity(input.len());
+    let mut comment = false;
+    let mut string = false;
+    for c in input.chars() {
+        match c {
+            '\n' => comment = false,
+            '#' if !string => comment = true,
+            '\'' if !comment => {
+                string = !string;
+                buf.push('"');
+                continue;
+            }
+            '"' if string => {
+                buf.push_str("\\\"");
+                continue;
+            }
+            _ => {}
+        }
+        buf.push(c);
+    }
+    buf
+}
+
+// Module names are relative to the directory of the main schema file, the
+// same way QEMU names them
+fn load_module(
+    root: &Path,
+    module: &Path,
+    seen: &mut HashSet<PathBuf>,
+    sections: &mut Vec<Section>,
+) -> Result<(), String> {
+    if !seen.insert(module.to_path_buf()) {
+        return Ok(());
+    }
+    let path = root.join(module);
+    let mut buf = String::new();
+    File::open(&path)
+        .and_then(|mut f| f.read_to_string(&mut buf))
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    let parsed = Schema::parse(&buf).map_err(|e| format!("{}: {}", path.display(), e))?;
+    for mut section in parsed.sections {
+        if let QemuType::Include { ref name } = section.qemu_type {
+            // Includes are relative to the file that includes them
+            let include = match module.parent() {
+                Some(dir) => dir.join(name),
+                None => PathBuf::from(name),
+            };
+            load_module(root, &include, seen, sections)?;
+        }
+        section.module = Some(module.to_string_lossy().into_owned());
+        sections.push(section);
+    }
+    Ok(())
+}
+
+/// A parsed QAPI schema with every definition in the order it appeared
+#[derive(Clone, Debug, PartialEq)]
+pub struct Schema {
+    pub sections: Vec<Section>,
+}
+
+impl Schema {
+    /// Parse QAPI source text.  Single quotes are converted for you.
+    pub fn parse(input: &str) -> Result<Schema, String> {
+        let input = double_quotes(input);
+        match sections_from_text(input.as_bytes()) {
+            nom::IResult::Done(_, sections) => Ok(Schema::from_sections(sections?)),
+            nom::IResult::Incomplete(needed) => Err(format!("Incomplete: {:?}", needed)),
+            nom::IResult::Error(e) => Err(format!("Error: {:?}", e)),
+        }
+    }
+
+    pub fn from_sections(sections: Vec<Section>) -> Schema {
+        Schema { sections }
+    }
+
+    /// Parse a schema file along with every file it includes.  Included
+    /// files come before the file that includes them, and each file is only
+    /// read once.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Schema, String> {
+        let path = path.as_ref();
+        let module = match path.file_name() {
+            Some(n) => PathBuf::from(n),
+            None => return Err(format!("Not a schema file: {}", path.display())),
+        };
+        let root = path.parent().unwrap_or_else(|| Path::new(""));
+        let mut seen: HashSet<PathBuf> = HashSet::new();
+        let mut sections: Vec<Section> = Vec::new();
+        load_module(root, &module, &mut seen, &mut sections)?;
+        Ok(Schema { sections })
+    }
+
+    /// Build a schema from already decoded definitions.  The sections won't
+    /// carry any description.
+    pub fn from_definitions(definitions: &[Value]) -> Result<Schema, String> {
+        Ok(Schema {
+            sections: definitions
+                .iter()
+                .map(|d| {
+                    Ok(Section {
+                        description: Vec::new(),
+                        qemu_type: QemuType::parse(d.clone())?,
+                        module: None,
+                    })
+                })
+                .collect::<Result<Vec<Section>, String>>()?,
+        })
+    }
+
+    /// Every named definition, in schema order
+    pub fn definitions(&self) -> Vec<&QemuType> {
+        self.sections
+            .iter()
+            .map(|s| &s.qemu_type)
+            .filter(|t| t.name().is_some())
+            .collect()
+    }
+
+    /// Look up a definition by name
+    pub fn get(&self, name: &str) -> Option<&QemuType> {
+        self.sections
+            .iter()
+            .map(|s| &s.qemu_type)
+            .find(|t| t.name() == Some(name))
+    }
+
+    /// Every definition that uses the type, directly or through other types,
+    /// in schema order
+    pub fn users_of(&self, name: &str) -> Vec<String> {
+        let graph = DependencyGraph::new(self);
+        let users = graph.users_of(name);
+        graph
+            .definitions()
+            .iter()
+            .filter(|n| users.contains(*n))
+            .cloned()
+            .collect()
+    }
+
+    /// Every type a definition refers to, directly or through other types,
+    /// in schema order.  Types that aren't defined in the schema come last.
+    pub fn reachable_from(&self, name: &str) -> Vec<String> {
+        let graph = DependencyGraph::new(self);
+        let mut reachable = graph.reachable_from(name);
+        let mut types: Vec<String> = graph
+            .definitions()
+            .iter()
+            .filter(|n| reachable.remove(*n))
+            .cloned()
+            .collect();
+        types.extend(reachable);
+        types
+    }
+
+    fn commands_and_events(&self, is_match: &dyn Fn(&QemuType) -> bool) -> Vec<String> {
+        self.definitions()
+            .into_iter()
+            .filter(|t| is_match(t))
+            .map(|t| t.name().unwrap().to_string())
+            .collect()
+    }
+
+    /// Commands whose return value contains the type
+    pub fn commands_returning(&self, name: &str) -> Vec<String> {
+        let graph = DependencyGraph::new(self);
+        self.commands_and_events(&|t| match t {
+            QemuType::Command(c) => graph.refers_to(&c.name, DependencyKind::Returns, name),
+            _ => false,
+        })
+    }
+
+    /// Commands whose arguments contain the type
+    pub fn commands_accepting(&self, name: &str) -> Vec<String> {
+        let graph = DependencyGraph::new(self);
+        self.commands_and_events(&|t| match t {
+            QemuType::Command(c) => graph.refers_to(&c.name, DependencyKind::Arguments, name),
+            _ => false,
+        })
+    }
+
+    /// Events whose data contains the type
+    pub fn events_carrying(&self, name: &str) -> Vec<String> {
+        let graph = DependencyGraph::new(self);
+        self.commands_and_events(&|t| match t {
+            QemuType::Event(e) => graph.refers_to(&e.name, DependencyKind::Data, name),
+            _ => false,
+        })
+    }
+}
+
+#[test]
+fn test_parse_keeps_comment_quotes() {
+    let schema = Schema::parse(
+        r#"# -*- Mode: Python -*-
+
+##
+# @BLOCK_IMAGE_CORRUPTED:
+#
+# @offset: the host's access offset into the image, '#' marks
+##
+{ 'event': 'BLOCK_IMAGE_CORRUPTED', 'data': { 'device': 'str', '*offset': 'int' } }
+"#,
+    ).unwrap();
+    assert_eq!(
+        schema.sections[0].description[3],
+        " @offset: the host's access offset into the image, '#' marks"
+    );
+    assert!(schema.get("BLOCK_IMAGE_CORRUPTED").is_some());
+
+    let schema = Schema::parse("{ 'enum': 'Quote', 'data': [ 'say \"hi\"' ] }\n").unwrap();
+    match schema.get("Quote") {
+        Some(QemuType::Enum(e)) => assert_eq!(e.fields[0], "say \"hi\""),
+        other => panic!("Expected an enum: {:?}", other),
+    }
+}
+
+#[test]
+fn test_parse_errors() {
+    let error = Schema::parse("{ 'struct': 'Foo', 'data': { 'a': 'int', } }\n").unwrap_err();
+    assert!(error.starts_with("Invalid definition { \"struct\": \"Foo\""), "{}", error);
+    assert!(error.contains("trailing comma"), "{}", error);
+
+    assert_eq!(
+        Schema::parse("{ 'enum': 'Ok', 'data': [] }\n{ 'struct': 1, 'data': {} }\n").unwrap_err(),
+        "The name of struct 1 is not a string"
+    );
+    assert!(Schema::parse("{ 'command': 'stop'\n").is_err());
+    let definitions: Vec<Value> = vec![serde_json::from_str(r#"{"event": ["STOP"]}"#).unwrap()];
+    assert!(Schema::from_definitions(&definitions).is_err());
+
+    // Comments after the last definition are fine
+    let schema = Schema::parse("{ 'command': 'stop' }\n# the end\n").unwrap();
+    assert_eq!(schema.sections.len(), 1);
+}
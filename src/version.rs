@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use schema::Schema;
+
+/// A QEMU release as written in the "Since:" sections of the schema
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct QemuVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub micro: u32,
+}
+
+impl QemuVersion {
+    pub fn new(major: u32, minor: u32, micro: u32) -> QemuVersion {
+        QemuVersion {
+            major,
+            minor,
+            micro,
+        }
+    }
+
+    /// Find the version in free text like "2.1", "0.14.0" or "(since 1.7)".
+    /// The first thing that looks like a version wins.
+    pub fn find(input: &str) -> Option<QemuVersion> {
+        input
+            .split(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .filter(|word| word.contains('.'))
+            .filter_map(|word| word.trim_matches('.').parse().ok())
+            .next()
+    }
+}
+
+impl FromStr for QemuVersion {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = input.trim().split('.').collect();
+        if parts.len() < 2 || parts.len() > 3 {
+            return Err(format!("Not a QEMU version: {}", input));
+        }
+        let mut numbers = [0; 3];
+        for (i, part) in parts.iter().enumerate() {
+            numbers[i] = part
+                .parse()
+                .map_err(|_| format!("Not a QEMU version: {}", input))?;
+        }
+        Ok(QemuVersion::new(numbers[0], numbers[1], numbers[2]))
+    }
+}
+
+impl fmt::Display for QemuVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.micro)
+    }
+}
+
+/// The QEMU release every definition and member first appeared in,
+/// according to the schema documentation
+#[derive(Clone, Debug, Default)]
+pub struct SinceIndex {
+    order: Vec<String>,
+    definitions: HashMap<String, QemuVersion>,
+    members: HashMap<(String, String), QemuVersion>,
+}
+
+impl SinceIndex {
+    pub fn new(schema: &Schema) -> SinceIndex {
+        let mut index = SinceIndex::default();
+        for section in &schema.sections {
+            let name = match section.qemu_type.name() {
+                Some(n) => n.to_string(),
+                None => continue,
+            };
+            let doc = section.doc();
+            if let Some(version) = doc.version_since {
+                index.definitions.insert(name.clone(), version);
+            }
+            for (member, text) in doc.parameters.unwrap_or_default() {
+                if let Some(version) = member_since(&text) {
+                    index.members.insert((name.clone(), member), version);
+                }
+            }
+            index.order.push(name);
+        }
+        index
+    }
+
+    /// The release a definition first appeared in
+    pub fn since(&self, name: &str) -> Option<QemuVersion> {
+        self.definitions.get(name).cloned()
+    }
+
+    /// The oldest release that has both the definition and the member.
+    /// Members without their own "(since X.Y)" note are as old as the
+    /// definition.
+    pub fn minimum_version(&self, name: &str, member: &str) -> Option<QemuVersion> {
+        let member = self
+            .members
+            .get(&(name.to_string(), member.to_string()))
+            .cloned();
+        match (self.since(name), member) {
+            (Some(d), Some(m)) => Some(if m > d { m } else { d }),
+            (d, m) => d.or(m),
+        }
+    }
+
+    /// Definitions that exist in the given release.  Definitions without a
+    /// documented version are assumed to always have been there.
+    pub fn available_in(&self, version: QemuVersion) -> Vec<&str> {
+        self.order
+            .iter()
+            .filter(|n| self.since(n).map_or(true, |v| v <= version))
+            .map(|n| n.as_str())
+            .collect()
+    }
+
+    /// Definitions that exist in `newer` but not yet in `older`
+    pub fn added_between(&self, older: QemuVersion, newer: QemuVersion) -> Vec<&str> {
+        self.order
+            .iter()
+            .filter(|n| self.since(n).is_some_and(|v| older < v && v <= newer))
+            .map(|n| n.as_str())
+            .collect()
+    }
+}
+
+// Members note when they were added with "(since X.Y)" or "(Since: X.Y)"
+// somewhere in their description
+fn member_since(text: &str) -> Option<QemuVersion> {
+    let lower = text.to_lowercase();
+    lower.match_indices("since").find_map(|(i, _)| {
+        let rest = &lower[i + 5..];
+        let rest = rest.strip_prefix(':').unwrap_or(rest);
+        if rest.starts_with(' ') {
+            QemuVersion::find(rest)
+        } else {
+            None
+        }
+    })
+}
+
+#[test]
+fn test_parse_version() {
+    assert_eq!("2.1".parse::<QemuVersion>(), Ok(QemuVersion::new(2, 1, 0)));
+    assert_eq!(
+        "0.14.0".parse::<QemuVersion>(),
+        Ok(QemuVersion::new(0, 14, 0))
+    );
+    assert!("2".parse::<QemuVersion>().is_err());
+    assert!("two.one".parse::<QemuVersion>().is_err());
+    assert!(QemuVersion::new(0, 14, 0) < QemuVersion::new(1, 2, 0));
+    assert_eq!(
+        QemuVersion::find("data (since 2.5)"),
+        Some(QemuVersion::new(2, 5, 0))
+    );
+    assert_eq!(QemuVersion::new(6, 2, 0).to_string(), "6.2.0");
+}
+
+#[test]
+fn test_since_index() {
+    let input = r#"{ 'include': 'common.json' }
+##
+# @MigrationStats:
+#
+# @transferred: amount of bytes already transferred to the target VM
+#
+# @mbps: throughput in megabits/sec. (since 1.6)
+#
+# @node-name: #optional node name (Since: 2.4)
+#
+# Since: 0.14.0
+##
+{ 'struct': 'MigrationStats',
+  'data': {'transferred': 'int', 'mbps' : 'number', '*node-name': 'str' } }
+##
+# @query-hotpluggable-cpus:
+#
+# Since: 2.7
+##
+{ 'command': 'query-hotpluggable-cpus', 'returns': ['MigrationStats'] }
+"#;
+    let schema = Schema::parse(input).unwrap();
+    let index = SinceIndex::new(&schema);
+    assert_eq!(
+        index.since("MigrationStats"),
+        Some(QemuVersion::new(0, 14, 0))
+    );
+    assert_eq!(
+        index.minimum_version("MigrationStats", "mbps"),
+        Some(QemuVersion::new(1, 6, 0))
+    );
+    assert_eq!(
+        index.minimum_version("MigrationStats", "node-name"),
+        Some(QemuVersion::new(2, 4, 0))
+    );
+    assert_eq!(
+        index.minimum_version("MigrationStats", "transferred"),
+        Some(QemuVersion::new(0, 14, 0))
+    );
+    assert_eq!(
+        index.available_in(QemuVersion::new(2, 0, 0)),
+        vec!["MigrationStats"]
+    );
+    assert_eq!(
+        index.added_between(QemuVersion::new(2, 0, 0), QemuVersion::new(2, 7, 0)),
+        vec!["query-hotpluggable-cpus"]
+    );
+}
@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+
+use graph::DependencyGraph;
+use schema::Schema;
+use {Alternate, Command, Enum, Event, QemuType, Section, Struct, Union};
+
+/// Callbacks for walking a schema, modeled after QEMU's QAPISchemaVisitor.
+/// Every callback does nothing by default so a backend only needs to
+/// implement the entities it cares about.  The description is the comment
+/// block in front of the definition.
+///
+/// Modules are visited in the order they were loaded, which puts included
+/// files before the files that include them.  Inside a module definitions
+/// come after the definitions they depend on.
+pub trait SchemaVisitor {
+    fn visit_begin(&mut self, _schema: &Schema) {}
+    fn visit_end(&mut self) {}
+    /// Called before the definitions of each file loaded by `Schema::load`
+    fn visit_module(&mut self, _name: &str) {}
+    fn visit_include(&mut self, _name: &str) {}
+    fn visit_enum(&mut self, _e: &Enum, _description: &[String]) {}
+    fn visit_struct(&mut self, _s: &Struct, _description: &[String]) {}
+    fn visit_union(&mut self, _u: &Union, _description: &[String]) {}
+    fn visit_alternate(&mut self, _a: &Alternate, _description: &[String]) {}
+    fn visit_command(&mut self, _c: &Command, _description: &[String]) {}
+    fn visit_event(&mut self, _e: &Event, _description: &[String]) {}
+}
+
+fn visit_section(visitor: &mut dyn SchemaVisitor, section: &Section) {
+    let description = &section.description;
+    match section.qemu_type {
+        QemuType::Enum(ref e) => visitor.visit_enum(e, description),
+        QemuType::Struct(ref s) => visitor.visit_struct(s, description),
+        QemuType::Union(ref u) => visitor.visit_union(u, description),
+        QemuType::Alternate(ref a) => visitor.visit_alternate(a, description),
+        QemuType::Command(ref c) => visitor.visit_command(c, description),
+        QemuType::Event(ref e) => visitor.visit_event(e, description),
+        QemuType::Include { ref name } => visitor.visit_include(name),
+        QemuType::Unknown => {}
+    }
+}
+
+impl Schema {
+    /// Walk every definition in the schema with the visitor
+    pub fn visit(&self, visitor: &mut dyn SchemaVisitor) {
+        let graph = DependencyGraph::new(self);
+        let mut order: HashMap<String, usize> = HashMap::new();
+        for (i, name) in graph
+            .strongly_connected_components()
+            .into_iter()
+            .flat_map(|c| c.into_iter())
+            .enumerate()
+        {
+            order.insert(name, i);
+        }
+
+        // A module is done once its last section has been seen, and by then
+        // everything it includes is done as well
+        let mut modules: Vec<&Option<String>> = Vec::new();
+        for section in self.sections.iter().rev() {
+            if !modules.contains(&&section.module) {
+                modules.push(&section.module);
+            }
+        }
+        modules.reverse();
+
+        visitor.visit_begin(self);
+        for module in modules {
+            if let Some(ref name) = *module {
+                visitor.visit_module(name);
+            }
+            let mut sections: Vec<&Section> = self
+                .sections
+                .iter()
+                .filter(|s| &s.module == module)
+                .collect();
+            // Includes and anything else without a name stay up front
+            sections.sort_by_key(|s| match s.qemu_type.name() {
+                Some(name) => order[name] + 1,
+                None => 0,
+            });
+            for section in sections {
+                visit_section(visitor, section);
+            }
+        }
+        visitor.visit_end();
+    }
+}
+
+#[cfg(test)]
+#[derive(Default)]
+struct Recorder {
+    calls: Vec<String>,
+}
+
+#[cfg(test)]
+impl SchemaVisitor for Recorder {
+    fn visit_module(&mut self, name: &str) {
+        self.calls.push(format!("module {}", name));
+    }
+    fn visit_include(&mut self, name: &str) {
+        self.calls.push(format!("include {}", name));
+    }
+    fn visit_enum(&mut self, e: &Enum, _description: &[String]) {
+        self.calls.push(format!("enum {}", e.name));
+    }
+    fn visit_struct(&mut self, s: &Struct, _description: &[String]) {
+        self.calls.push(format!("struct {}", s.name));
+    }
+    fn visit_command(&mut self, c: &Command, description: &[String]) {
+        self.calls.push(format!(
+            "command {} {}",
+            c.name,
+            description.join("").trim()
+        ));
+    }
+}
+
+#[test]
+fn test_visit_dependency_order() {
+    let input = r#"{ 'enum': 'Color', 'data': [ 'red', 'green' ] }
+##
+# @query-paint:
+##
+{ 'command': 'query-paint', 'returns': ['Paint'] }
+{ 'struct': 'Paint', 'data': { 'color': 'Color' } }
+"#;
+    let schema = Schema::parse(input).unwrap();
+    let mut recorder = Recorder::default();
+    schema.visit(&mut recorder);
+    assert_eq!(
+        recorder.calls,
+        vec![
+            "enum Color",
+            "struct Paint",
+            "command query-paint # @query-paint:#",
+        ]
+    );
+}
+
+#[test]
+fn test_visit_modules() {
+    let schema = Schema::load("tests/block.json").unwrap();
+    let mut recorder = Recorder::default();
+    schema.visit(&mut recorder);
+
+    let modules: Vec<&String> = recorder
+        .calls
+        .iter()
+        .filter(|c| c.starts_with("module") || c.starts_with("include"))
+        .collect();
+    assert_eq!(
+        modules,
+        vec![
+            "module common.json",
+            "module block-core.json",
+            "include common.json",
+            "module block.json",
+            "include block-core.json",
+        ]
+    );
+}
@@ -0,0 +1,2576 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// A QMP command.  The command serializes to the "arguments" of
+/// an "execute" request, and the "return" of a successful reply
+/// deserializes to `Ok`.
+pub trait QmpCommand: Serialize {
+    type Ok: DeserializeOwned;
+    /// The name to "execute"
+    const NAME: &'static str;
+    /// Whether the command may be run out-of-band
+    const ALLOW_OOB: bool;
+    /// Whether QEMU replies to the command at all
+    const SUCCESS_RESPONSE: bool;
+}
+
+/// The "return" of commands that don't return anything
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Empty {}
+
+/// QEMU error classes
+///
+/// # Since
+///
+/// 1.2.0
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorClass {
+    /// this is used for errors that don't require a specific error class. This should be the default case for most errors
+    #[serde(rename = "GenericError")]
+    GenericError,
+    /// the requested command has not been found
+    #[serde(rename = "CommandNotFound")]
+    CommandNotFound,
+    /// the requested operation can't be fulfilled because the selected device is encrypted
+    #[serde(rename = "DeviceEncrypted")]
+    DeviceEncrypted,
+    /// a device has failed to be become active
+    #[serde(rename = "DeviceNotActive")]
+    DeviceNotActive,
+    /// the requested device has not been found
+    #[serde(rename = "DeviceNotFound")]
+    DeviceNotFound,
+    /// the requested operation can't be fulfilled because a required KVM capability is missing
+    #[serde(rename = "KVMMissingCap")]
+    KVMMissingCap,
+}
+
+/// A three-part version number.
+///
+/// # Since
+///
+/// 2.4.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct VersionTriple {
+    pub major: i64,
+    pub minor: i64,
+    pub micro: i64,
+}
+
+/// A description of QEMU's version.
+///
+/// # Since
+///
+/// 0.14.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct VersionInfo {
+    /// The version of QEMU.  By current convention, a micro version of 50 signifies a development branch.  A micro version greater than or equal to 90 signifies a release candidate for the next minor version.  A micro version of less than 50 signifies a stable release.
+    pub qemu: VersionTriple,
+    /// QEMU will always set this field to an empty string.  Downstream versions of QEMU should set this to a non-empty string.  The exact format depends on the downstream however it highly recommended that a unique name is used.
+    pub package: String,
+}
+
+/// Returns the current version of QEMU.
+///
+/// # Returns
+///
+/// A [`VersionInfo`] object describing the current version of QEMU.
+///
+/// # Since
+///
+/// 0.14.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct QueryVersionCommand {}
+
+impl QmpCommand for QueryVersionCommand {
+    type Ok = VersionInfo;
+    const NAME: &'static str = "query-version";
+    const ALLOW_OOB: bool = false;
+    const SUCCESS_RESPONSE: bool = true;
+}
+
+/// Information about a QMP command
+///
+/// # Since
+///
+/// 0.14.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CommandInfo {
+    /// The command name
+    pub name: String,
+}
+
+/// Return a list of supported QMP commands by this server
+///
+/// # Returns
+///
+/// A list of [`CommandInfo`] for all supported commands
+///
+/// # Since
+///
+/// 0.14.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct QueryCommandsCommand {}
+
+impl QmpCommand for QueryCommandsCommand {
+    type Ok = Vec<CommandInfo>;
+    const NAME: &'static str = "query-commands";
+    const ALLOW_OOB: bool = false;
+    const SUCCESS_RESPONSE: bool = true;
+}
+
+/// An enumeration of three options: on, off, and auto
+///
+/// # Since
+///
+/// 2.2.0
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OnOffAuto {
+    /// QEMU selects the value between on and off
+    Auto,
+    /// Enabled
+    On,
+    /// Disabled
+    Off,
+}
+
+/// # Since
+///
+/// 1.3.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SnapshotInfo {
+    /// unique snapshot id
+    pub id: String,
+    /// user chosen name
+    pub name: String,
+    /// size of the VM state
+    pub vm_state_size: i64,
+    /// UTC date of the snapshot in seconds
+    pub date_sec: i64,
+    /// fractional part in nano seconds to be used with date-sec
+    pub date_nsec: i64,
+    /// VM clock relative to boot in seconds
+    pub vm_clock_sec: i64,
+    /// fractional part in nano seconds to be used with vm-clock-sec
+    pub vm_clock_nsec: i64,
+}
+
+/// # Since
+///
+/// 1.7.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ImageInfoSpecificQCow2 {
+    /// compatibility level
+    pub compat: String,
+    /// on or off; only valid for compat >= 1.1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lazy_refcounts: Option<bool>,
+    /// true if the image has been marked corrupt; only valid for compat >= 1.1 (since 2.2)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub corrupt: Option<bool>,
+    /// width of a refcount entry in bits (since 2.3)
+    pub refcount_bits: i64,
+}
+
+/// # Since
+///
+/// 1.7.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ImageInfoSpecificVmdk {
+    /// The create type of VMDK image
+    pub create_type: String,
+    /// Content id of image
+    pub cid: i64,
+    /// Parent VMDK image's cid
+    pub parent_cid: i64,
+    /// List of extent files
+    pub extents: Vec<ImageInfo>,
+}
+
+/// Information about a QEMU image file
+///
+/// # Since
+///
+/// 1.3.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ImageInfo {
+    /// name of the image file
+    pub filename: String,
+    /// format of the image file
+    pub format: String,
+    /// true if image is not cleanly closed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dirty_flag: Option<bool>,
+    /// actual size on disk in bytes of the image
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actual_size: Option<i64>,
+    /// maximum capacity in bytes of the image
+    pub virtual_size: i64,
+    /// size of a cluster in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cluster_size: Option<i64>,
+    /// true if the image is encrypted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encrypted: Option<bool>,
+    /// true if the image is compressed (Since 1.7)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compressed: Option<bool>,
+    /// name of the backing file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backing_filename: Option<String>,
+    /// full path of the backing file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub full_backing_filename: Option<String>,
+    /// the format of the backing file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backing_filename_format: Option<String>,
+    /// list of VM snapshots
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshots: Option<Vec<SnapshotInfo>>,
+    /// info of the backing image (since 1.6)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backing_image: Option<Box<ImageInfo>>,
+    /// structure supplying additional format-specific information (since 1.7)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format_specific: Option<ImageInfoSpecific>,
+}
+
+/// A discriminated record of image format specific information structures.
+///
+/// # Since
+///
+/// 1.7.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "kebab-case")]
+pub enum ImageInfoSpecific {
+    Qcow2(ImageInfoSpecificQCow2),
+    Vmdk(ImageInfoSpecificVmdk),
+}
+
+/// Information about a QEMU image file check
+///
+/// # Since
+///
+/// 1.4.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ImageCheck {
+    /// name of the image file checked
+    pub filename: String,
+    /// format of the image file checked
+    pub format: String,
+    /// number of unexpected errors occurred during check
+    pub check_errors: i64,
+    /// offset (in bytes) where the image ends, this field is present if the driver for the image format supports it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_end_offset: Option<i64>,
+    /// number of corruptions found during the check if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub corruptions: Option<i64>,
+    /// number of leaks found during the check if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub leaks: Option<i64>,
+    /// number of corruptions fixed during the check if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub corruptions_fixed: Option<i64>,
+    /// number of leaks fixed during the check if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub leaks_fixed: Option<i64>,
+    /// total number of clusters, this field is present if the driver for the image format supports it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_clusters: Option<i64>,
+    /// total number of allocated clusters, this field is present if the driver for the image format supports it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allocated_clusters: Option<i64>,
+    /// total number of fragmented clusters, this field is present if the driver for the image format supports it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fragmented_clusters: Option<i64>,
+    /// total number of compressed clusters, this field is present if the driver for the image format supports it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compressed_clusters: Option<i64>,
+}
+
+/// Cache mode information for a block device
+///
+/// # Since
+///
+/// 2.3.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockdevCacheInfo {
+    /// true if writeback mode is enabled
+    pub writeback: bool,
+    /// true if the host page cache is bypassed (O_DIRECT)
+    pub direct: bool,
+    /// true if flush requests are ignored for the device
+    pub no_flush: bool,
+}
+
+/// Describes the operation mode for the automatic conversion of plain
+/// zero writes by the OS to driver specific optimized zero write commands.
+///
+/// # Since
+///
+/// 2.1.0
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BlockdevDetectZeroesOptions {
+    /// Disabled (default)
+    Off,
+    /// Enabled
+    On,
+    /// Enabled and even try to unmap blocks if possible. This requires also that [`BlockdevDiscardOptions`] is set to unmap for this device.
+    Unmap,
+}
+
+/// Information about the backing device for a block device.
+///
+/// # Since
+///
+/// 0.14.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockDeviceInfo {
+    /// the filename of the backing device
+    pub file: String,
+    /// the name of the block driver node (Since 2.0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_name: Option<String>,
+    /// true if the backing device was open read-only
+    pub ro: bool,
+    /// the name of the block format used to open the backing device. As of 0.14.0 this can be: 'blkdebug', 'bochs', 'cloop', 'cow', 'dmg', 'file', 'file', 'ftp', 'ftps', 'host_cdrom', 'host_device', 'host_floppy', 'http', 'https', 'nbd', 'parallels', 'qcow', 'qcow2', 'raw', 'tftp', 'vdi', 'vmdk', 'vpc', 'vvfat' 2.2: 'archipelago' added, 'cow' dropped 2.3: 'host_floppy' deprecated
+    pub drv: String,
+    /// the name of the backing file (for copy-on-write)
+    #[serde(rename = "backing_file")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backing_file: Option<String>,
+    /// number of files in the backing file chain (since: 1.2)
+    #[serde(rename = "backing_file_depth")]
+    pub backing_file_depth: i64,
+    /// true if the backing device is encrypted
+    pub encrypted: bool,
+    /// true if the backing device is encrypted but an valid encryption key is missing
+    #[serde(rename = "encryption_key_missing")]
+    pub encryption_key_missing: bool,
+    /// detect and optimize zero writes (Since 2.1)
+    #[serde(rename = "detect_zeroes")]
+    pub detect_zeroes: BlockdevDetectZeroesOptions,
+    /// total throughput limit in bytes per second is specified
+    pub bps: i64,
+    /// read throughput limit in bytes per second is specified
+    #[serde(rename = "bps_rd")]
+    pub bps_rd: i64,
+    /// write throughput limit in bytes per second is specified
+    #[serde(rename = "bps_wr")]
+    pub bps_wr: i64,
+    /// total I/O operations per second is specified
+    pub iops: i64,
+    /// read I/O operations per second is specified
+    #[serde(rename = "iops_rd")]
+    pub iops_rd: i64,
+    /// write I/O operations per second is specified
+    #[serde(rename = "iops_wr")]
+    pub iops_wr: i64,
+    /// the info of image used (since: 1.6)
+    pub image: ImageInfo,
+    /// total max in bytes (Since 1.7)
+    #[serde(rename = "bps_max")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bps_max: Option<i64>,
+    /// read max in bytes (Since 1.7)
+    #[serde(rename = "bps_rd_max")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bps_rd_max: Option<i64>,
+    /// write max in bytes (Since 1.7)
+    #[serde(rename = "bps_wr_max")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bps_wr_max: Option<i64>,
+    /// total I/O operations max (Since 1.7)
+    #[serde(rename = "iops_max")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iops_max: Option<i64>,
+    /// read I/O operations max (Since 1.7)
+    #[serde(rename = "iops_rd_max")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iops_rd_max: Option<i64>,
+    /// write I/O operations max (Since 1.7)
+    #[serde(rename = "iops_wr_max")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iops_wr_max: Option<i64>,
+    /// an I/O size in bytes (Since 1.7)
+    #[serde(rename = "iops_size")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iops_size: Option<i64>,
+    /// the cache mode used for the block device (since: 2.3)
+    pub cache: BlockdevCacheInfo,
+    /// configured write threshold for the device. 0 if disabled. (Since 2.3)
+    #[serde(rename = "write_threshold")]
+    pub write_threshold: i64,
+}
+
+/// An enumeration of block device I/O status.
+///
+/// # Since
+///
+/// 1.0.0
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BlockDeviceIoStatus {
+    /// The last I/O operation has succeeded
+    Ok,
+    /// The last I/O operation has failed
+    Failed,
+    /// The last I/O operation has failed due to a no-space condition
+    Nospace,
+}
+
+/// Entry in the metadata map of the device (returned by "qemu-img map")
+///
+/// # Since
+///
+/// 1.7.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockDeviceMapEntry {
+    /// Offset in the image of the first byte described by this entry (in bytes)
+    pub start: i64,
+    /// Length of the range described by this entry (in bytes)
+    pub length: i64,
+    /// Number of layers (0 = top image, 1 = top image's backing file, etc.) before reaching one for which the range is allocated.  The value is in the range 0 to the depth of the image chain - 1.
+    pub depth: i64,
+    /// the sectors in this range read as zeros
+    pub zero: bool,
+    /// reading the image will actually read data from a file (in particular, if [`offset`](BlockDeviceMapEntry::offset) is present this means that the sectors are not simply preallocated, but contain actual data in raw format)
+    pub data: bool,
+    /// if present, the image file stores the data for this range in raw format at the given offset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<i64>,
+}
+
+/// Block dirty bitmap information.
+///
+/// # Since
+///
+/// 1.3.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockDirtyInfo {
+    /// the name of the dirty bitmap (Since 2.4)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// number of dirty bytes according to the dirty bitmap
+    pub count: i64,
+    /// granularity of the dirty bitmap in bytes (since 1.4)
+    pub granularity: u32,
+    /// whether the dirty bitmap is frozen (Since 2.4)
+    pub frozen: bool,
+}
+
+/// Block device information.  This structure describes a virtual device and
+/// the backing device associated with it.
+///
+/// # Since
+///
+/// 0.14.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockInfo {
+    /// The device name associated with the virtual device.
+    pub device: String,
+    /// This field is returned only for compatibility reasons, it should not be used (always returns 'unknown')
+    #[serde(rename = "type")]
+    pub qemu_type: String,
+    /// True if the device supports removable media.
+    pub removable: bool,
+    /// True if the guest has locked this device from having its media removed
+    pub locked: bool,
+    /// [`BlockDeviceInfo`] describing the device if media is present
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inserted: Option<BlockDeviceInfo>,
+    /// True if the device has a tray and it is open (only present if removable is true)
+    #[serde(rename = "tray_open")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tray_open: Option<bool>,
+    /// [`BlockDeviceIoStatus`]. Only present if the device supports it and the VM is configured to stop on errors (supported device models: virtio-blk, ide, scsi-disk)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub io_status: Option<BlockDeviceIoStatus>,
+    /// dirty bitmaps information (only present if the driver has one or more dirty bitmaps) (Since 2.0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dirty_bitmaps: Option<Vec<BlockDirtyInfo>>,
+}
+
+/// Get a list of BlockInfo for all virtual block devices.
+///
+/// # Returns
+///
+/// a list of [`BlockInfo`] describing each virtual block device
+///
+/// # Since
+///
+/// 0.14.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct QueryBlockCommand {}
+
+impl QmpCommand for QueryBlockCommand {
+    type Ok = Vec<BlockInfo>;
+    const NAME: &'static str = "query-block";
+    const ALLOW_OOB: bool = false;
+    const SUCCESS_RESPONSE: bool = true;
+}
+
+/// Statistics of a virtual block device or a block backing device.
+///
+/// # Since
+///
+/// 0.14.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockDeviceStats {
+    /// The number of bytes read by the device.
+    #[serde(rename = "rd_bytes")]
+    pub rd_bytes: i64,
+    /// The number of bytes written by the device.
+    #[serde(rename = "wr_bytes")]
+    pub wr_bytes: i64,
+    /// The number of read operations performed by the device.
+    #[serde(rename = "rd_operations")]
+    pub rd_operations: i64,
+    /// The number of write operations performed by the device.
+    #[serde(rename = "wr_operations")]
+    pub wr_operations: i64,
+    /// The number of cache flush operations performed by the device (since 0.15.0)
+    #[serde(rename = "flush_operations")]
+    pub flush_operations: i64,
+    /// Total time spend on cache flushes in nano-seconds (since 0.15.0).
+    #[serde(rename = "flush_total_time_ns")]
+    pub flush_total_time_ns: i64,
+    /// Total time spend on writes in nano-seconds (since 0.15.0).
+    #[serde(rename = "wr_total_time_ns")]
+    pub wr_total_time_ns: i64,
+    /// Total_time_spend on reads in nano-seconds (since 0.15.0).
+    #[serde(rename = "rd_total_time_ns")]
+    pub rd_total_time_ns: i64,
+    /// The offset after the greatest byte written to the device.  The intended use of this information is for growable sparse files (like qcow2) that are used on top of a physical device.
+    #[serde(rename = "wr_highest_offset")]
+    pub wr_highest_offset: i64,
+    /// Number of read requests that have been merged into another request (Since 2.3).
+    #[serde(rename = "rd_merged")]
+    pub rd_merged: i64,
+    /// Number of write requests that have been merged into another request (Since 2.3).
+    #[serde(rename = "wr_merged")]
+    pub wr_merged: i64,
+}
+
+/// Statistics of a virtual block device or a block backing device.
+///
+/// # Since
+///
+/// 0.14.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockStats {
+    /// If the stats are for a virtual block device, the name corresponding to the virtual block device.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+    /// The node name of the device. (Since 2.3)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_name: Option<String>,
+    /// A [`BlockDeviceStats`] for the device.
+    pub stats: BlockDeviceStats,
+    /// This describes the file block device if it has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<Box<BlockStats>>,
+    /// This describes the backing block device if it has one. (Since 2.0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backing: Option<Box<BlockStats>>,
+}
+
+/// Query the [`BlockStats`] for all virtual block devices.
+///
+/// # Returns
+///
+/// A list of [`BlockStats`] for each virtual block devices.
+///
+/// # Since
+///
+/// 0.14.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct QueryBlockstatsCommand {
+    /// If true, the command will query all the block nodes that have a node name, in a list which will include "parent" information, but not "backing". If false or omitted, the behavior is as before - query all the device backends, recursively including their "parent" and "backing". (Since 2.3)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query_nodes: Option<bool>,
+}
+
+impl QmpCommand for QueryBlockstatsCommand {
+    type Ok = Vec<BlockStats>;
+    const NAME: &'static str = "query-blockstats";
+    const ALLOW_OOB: bool = false;
+    const SUCCESS_RESPONSE: bool = true;
+}
+
+/// An enumeration of possible behaviors for errors on I/O operations.
+/// The exact meaning depends on whether the I/O was initiated by a guest
+/// or by a block job
+///
+/// # Since
+///
+/// 1.3.0
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BlockdevOnError {
+    /// for guest operations, report the error to the guest; for jobs, cancel the job
+    Report,
+    /// ignore the error, only report a QMP event (BLOCK_IO_ERROR or BLOCK_JOB_ERROR)
+    Ignore,
+    /// same as [`stop`](BlockdevOnError::Stop) on ENOSPC, same as [`report`](BlockdevOnError::Report) otherwise.
+    Enospc,
+    /// for guest operations, stop the virtual machine; for jobs, pause the job
+    Stop,
+}
+
+/// An enumeration of possible behaviors for the initial synchronization
+/// phase of storage mirroring.
+///
+/// # Since
+///
+/// 1.3.0
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MirrorSyncMode {
+    /// copies data in the topmost image to the destination
+    Top,
+    /// copies data from all images to the destination
+    Full,
+    /// only copy data written from now on
+    None,
+    /// only copy data described by the dirty bitmap. Since: 2.4
+    DirtyBitmap,
+}
+
+/// Type of a block job.
+///
+/// # Since
+///
+/// 1.7.0
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BlockJobType {
+    /// block commit job type, see "block-commit"
+    Commit,
+    /// block stream job type, see "block-stream"
+    Stream,
+    /// drive mirror job type, see "drive-mirror"
+    Mirror,
+    /// drive backup job type, see "drive-backup"
+    Backup,
+}
+
+/// Information about a long-running block device operation.
+///
+/// # Since
+///
+/// 1.1.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockJobInfo {
+    /// the job type ('stream' for image streaming)
+    #[serde(rename = "type")]
+    pub qemu_type: String,
+    /// the block device name
+    pub device: String,
+    /// the maximum progress value
+    pub len: i64,
+    /// the current progress value
+    pub offset: i64,
+    /// false if the job is known to be in a quiescent state, with no pending I/O.  Since 1.3.
+    pub busy: bool,
+    /// whether the job is paused or, if [`busy`](BlockJobInfo::busy) is true, will pause itself as soon as possible.  Since 1.3.
+    pub paused: bool,
+    /// the rate limit, bytes per second
+    pub speed: i64,
+    /// the status of the job (since 1.3)
+    pub io_status: BlockDeviceIoStatus,
+    /// true if the job may be completed (since 2.2)
+    pub ready: bool,
+}
+
+/// Return information about long-running block device operations.
+///
+/// # Returns
+///
+/// a list of [`BlockJobInfo`] for each active block job
+///
+/// # Since
+///
+/// 1.1.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct QueryBlockJobsCommand {}
+
+impl QmpCommand for QueryBlockJobsCommand {
+    type Ok = Vec<BlockJobInfo>;
+    const NAME: &'static str = "query-block-jobs";
+    const ALLOW_OOB: bool = false;
+    const SUCCESS_RESPONSE: bool = true;
+}
+
+/// This command sets the password of a block device that has not been open
+/// with a password and requires one.
+///
+/// The two cases where this can happen are a block device is created through
+/// QEMU's initial command line or a block device is changed through the legacy
+/// In the event that the block device is created through the initial command
+/// line, the VM will start in the stopped state regardless of whether '-S' is
+/// used.  The intention is for a management tool to query the block devices to
+/// determine which ones are encrypted, set the passwords with this command, and
+/// then start the guest with the `cont` command.
+///
+/// Either [`device`](BlockPasswdCommand::device) or [`node-name`](BlockPasswdCommand::node_name) must be set but not both.
+///
+/// # Returns
+///
+/// nothing on success
+///
+/// # Errors
+///
+/// - If [`device`](BlockPasswdCommand::device) is not a valid block device, DeviceNotFound
+/// - If [`device`](BlockPasswdCommand::device) is not encrypted, DeviceNotEncrypted
+///
+/// # Since
+///
+/// 0.14.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockPasswdCommand {
+    /// the name of the block backend device to set the password on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+    /// graph node name to set the password on (Since 2.0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_name: Option<String>,
+    /// the password to use for the device
+    pub password: String,
+}
+
+impl QmpCommand for BlockPasswdCommand {
+    type Ok = Empty;
+    const NAME: &'static str = "block_passwd";
+    const ALLOW_OOB: bool = false;
+    const SUCCESS_RESPONSE: bool = true;
+}
+
+/// Resize a block image while a guest is running.
+///
+/// Either [`device`](BlockResizeCommand::device) or [`node-name`](BlockResizeCommand::node_name) must be set but not both.
+///
+/// # Returns
+///
+/// nothing on success
+///
+/// # Errors
+///
+/// If [`device`](BlockResizeCommand::device) is not a valid block device, DeviceNotFound
+///
+/// # Since
+///
+/// 0.14.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockResizeCommand {
+    /// the name of the device to get the image resized
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+    /// graph node name to get the image resized (Since 2.0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_name: Option<String>,
+    /// new image size in bytes
+    pub size: i64,
+}
+
+impl QmpCommand for BlockResizeCommand {
+    type Ok = Empty;
+    const NAME: &'static str = "block_resize";
+    const ALLOW_OOB: bool = false;
+    const SUCCESS_RESPONSE: bool = true;
+}
+
+/// An enumeration that tells QEMU how to set the backing file path in
+/// a new image file.
+///
+/// # Since
+///
+/// 1.1.0
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NewImageMode {
+    /// QEMU should look for an existing image file.
+    Existing,
+    /// QEMU should create a new image with absolute paths for the backing file. If there is no backing file available, the new image will not be backed either.
+    AbsolutePaths,
+}
+
+/// Either [`device`](BlockdevSnapshot::device) or [`node-name`](BlockdevSnapshot::node_name) must be set but not both.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockdevSnapshot {
+    /// the name of the device to generate the snapshot from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+    /// graph node name to generate the snapshot from (Since 2.0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_name: Option<String>,
+    /// the target of the new image. A new file will be created.
+    pub snapshot_file: String,
+    /// the graph node name of the new image (Since 2.0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot_node_name: Option<String>,
+    /// the format of the snapshot image, default is 'qcow2'.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// whether and how QEMU should create a new image, default is 'absolute-paths'.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<NewImageMode>,
+}
+
+/// # Since
+///
+/// 1.6.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct DriveBackup {
+    /// the name of the device which should be copied.
+    pub device: String,
+    /// the target of the new image. If the file exists, or if it is a device, the existing file/device will be used as the new destination.  If it does not exist, a new file will be created.
+    pub target: String,
+    /// the format of the new destination, default is to probe if [`mode`](DriveBackup::mode) is 'existing', else the format of the source
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// what parts of the disk image should be copied to the destination (all the disk, only the sectors allocated in the topmost image, from a dirty bitmap, or only new I/O).
+    pub sync: MirrorSyncMode,
+    /// whether and how QEMU should create a new image, default is 'absolute-paths'.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<NewImageMode>,
+    /// the maximum speed, in bytes per second
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speed: Option<i64>,
+    /// the name of dirty bitmap if sync is "dirty-bitmap". Must be present if sync is "dirty-bitmap", must NOT be present otherwise. (Since 2.4)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bitmap: Option<String>,
+    /// the action to take on an error on the source, default 'report'.  'stop' and 'enospc' can only be used if the block device supports io-status (see BlockInfo).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_source_error: Option<BlockdevOnError>,
+    /// the action to take on an error on the target, default 'report' (no limitations, since this applies to a different block device than [`device`](DriveBackup::device)).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_target_error: Option<BlockdevOnError>,
+}
+
+/// # Since
+///
+/// 2.3.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockdevBackup {
+    /// the name of the device which should be copied.
+    pub device: String,
+    /// the name of the backup target device.
+    pub target: String,
+    /// what parts of the disk image should be copied to the destination (all the disk, only the sectors allocated in the topmost image, or only new I/O).
+    pub sync: MirrorSyncMode,
+    /// the maximum speed, in bytes per second. The default is 0, for unlimited.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speed: Option<i64>,
+    /// the action to take on an error on the source, default 'report'.  'stop' and 'enospc' can only be used if the block device supports io-status (see BlockInfo).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_source_error: Option<BlockdevOnError>,
+    /// the action to take on an error on the target, default 'report' (no limitations, since this applies to a different block device than [`device`](BlockdevBackup::device)).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_target_error: Option<BlockdevOnError>,
+}
+
+/// Generates a synchronous snapshot of a block device.
+///
+/// For the arguments, see the documentation of BlockdevSnapshot.
+///
+/// # Returns
+///
+/// nothing on success
+///
+/// # Errors
+///
+/// If [`device`](BlockdevSnapshotSyncCommand::device) is not a valid block device, DeviceNotFound
+///
+/// # Since
+///
+/// 0.14.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockdevSnapshotSyncCommand {
+    /// the name of the device to generate the snapshot from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+    /// graph node name to generate the snapshot from (Since 2.0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_name: Option<String>,
+    /// the target of the new image. A new file will be created.
+    pub snapshot_file: String,
+    /// the graph node name of the new image (Since 2.0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot_node_name: Option<String>,
+    /// the format of the snapshot image, default is 'qcow2'.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// whether and how QEMU should create a new image, default is 'absolute-paths'.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<NewImageMode>,
+}
+
+impl QmpCommand for BlockdevSnapshotSyncCommand {
+    type Ok = Empty;
+    const NAME: &'static str = "blockdev-snapshot-sync";
+    const ALLOW_OOB: bool = false;
+    const SUCCESS_RESPONSE: bool = true;
+}
+
+/// Change the backing file in the image file metadata.  This does not
+/// cause QEMU to reopen the image file to reparse the backing filename
+/// (it may, however, perform a reopen to change permissions from
+/// r/o -> r/w -> r/o, if needed). The new backing file string is written
+/// into the image file metadata, and the QEMU internal strings are
+/// updated.
+///
+/// # Since
+///
+/// 2.1.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ChangeBackingFileCommand {
+    /// The name of the device that owns image-node-name.
+    pub device: String,
+    /// The name of the block driver state node of the image to modify.
+    pub image_node_name: String,
+    /// The string to write as the backing file.  This string is not validated, so care should be taken when specifying the string or the image chain may not be able to be reopened again.
+    pub backing_file: String,
+}
+
+impl QmpCommand for ChangeBackingFileCommand {
+    type Ok = Empty;
+    const NAME: &'static str = "change-backing-file";
+    const ALLOW_OOB: bool = false;
+    const SUCCESS_RESPONSE: bool = true;
+}
+
+/// Live commit of data from overlay image nodes into backing nodes - i.e.,
+/// writes data between 'top' and 'base' into 'base'.
+///
+/// If a pathname string is such that it cannot be
+/// resolved by QEMU, that means that subsequent QMP or
+/// HMP commands must use node-names for the image in
+/// question, as filename lookup methods will fail.
+///
+/// If not specified, QEMU will automatically determine
+/// the backing file string to use, or error out if
+/// there is no obvious choice. Care should be taken
+/// when specifying the string, to specify a valid
+/// filename or protocol.
+/// (Since 2.1)
+///
+/// If top == base, that is an error.
+/// If top == active, the job will not be completed by itself,
+/// user needs to complete the job with the block-job-complete
+/// command after getting the ready event. (Since 2.0)
+///
+/// If the base image is smaller than top, then the base image
+/// will be resized to be the same size as top.  If top is
+/// smaller than the base image, the base will not be
+/// truncated.  If you want the base image size to match the
+/// size of the smaller top, you can safely truncate it
+/// yourself once the commit operation successfully completes.
+///
+/// # Returns
+///
+/// Nothing on success
+///
+/// # Errors
+///
+/// - If commit or stream is already active on this device, DeviceInUse
+/// - If [`device`](BlockCommitCommand::device) does not exist, DeviceNotFound
+/// - If image commit is not supported by this device, NotSupported
+/// - If [`base`](BlockCommitCommand::base) or [`top`](BlockCommitCommand::top) is invalid, a generic error is returned
+/// - If [`speed`](BlockCommitCommand::speed) is invalid, InvalidParameter
+///
+/// # Since
+///
+/// 1.3.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockCommitCommand {
+    /// the name of the device
+    pub device: String,
+    /// The file name of the backing image to write data into. If not specified, this is the deepest backing image
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+    /// The file name of the backing image within the image chain, which contains the topmost data to be committed down. If not specified, this is the active layer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top: Option<String>,
+    /// The backing file string to write into the overlay image of 'top'.  If 'top' is the active layer, specifying a backing file string is an error. This filename is not validated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backing_file: Option<String>,
+    /// the maximum speed, in bytes per second
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speed: Option<i64>,
+}
+
+impl QmpCommand for BlockCommitCommand {
+    type Ok = Empty;
+    const NAME: &'static str = "block-commit";
+    const ALLOW_OOB: bool = false;
+    const SUCCESS_RESPONSE: bool = true;
+}
+
+/// Start a point-in-time copy of a block device to a new destination.  The
+/// status of ongoing drive-backup operations can be checked with
+/// query-block-jobs where the BlockJobInfo.type field has the value 'backup'.
+/// The operation can be stopped before it has completed using the
+/// block-job-cancel command.
+///
+/// For the arguments, see the documentation of DriveBackup.
+///
+/// # Returns
+///
+/// nothing on success
+///
+/// # Errors
+///
+/// If [`device`](DriveBackupCommand::device) is not a valid block device, DeviceNotFound
+///
+/// # Since
+///
+/// 1.6.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct DriveBackupCommand {
+    /// the name of the device which should be copied.
+    pub device: String,
+    /// the target of the new image. If the file exists, or if it is a device, the existing file/device will be used as the new destination.  If it does not exist, a new file will be created.
+    pub target: String,
+    /// the format of the new destination, default is to probe if [`mode`](DriveBackupCommand::mode) is 'existing', else the format of the source
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// what parts of the disk image should be copied to the destination (all the disk, only the sectors allocated in the topmost image, from a dirty bitmap, or only new I/O).
+    pub sync: MirrorSyncMode,
+    /// whether and how QEMU should create a new image, default is 'absolute-paths'.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<NewImageMode>,
+    /// the maximum speed, in bytes per second
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speed: Option<i64>,
+    /// the name of dirty bitmap if sync is "dirty-bitmap". Must be present if sync is "dirty-bitmap", must NOT be present otherwise. (Since 2.4)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bitmap: Option<String>,
+    /// the action to take on an error on the source, default 'report'.  'stop' and 'enospc' can only be used if the block device supports io-status (see BlockInfo).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_source_error: Option<BlockdevOnError>,
+    /// the action to take on an error on the target, default 'report' (no limitations, since this applies to a different block device than [`device`](DriveBackupCommand::device)).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_target_error: Option<BlockdevOnError>,
+}
+
+impl QmpCommand for DriveBackupCommand {
+    type Ok = Empty;
+    const NAME: &'static str = "drive-backup";
+    const ALLOW_OOB: bool = false;
+    const SUCCESS_RESPONSE: bool = true;
+}
+
+/// Start a point-in-time copy of a block device to a new destination.  The
+/// status of ongoing blockdev-backup operations can be checked with
+/// query-block-jobs where the BlockJobInfo.type field has the value 'backup'.
+/// The operation can be stopped before it has completed using the
+/// block-job-cancel command.
+///
+/// For the arguments, see the documentation of BlockdevBackup.
+///
+/// # Since
+///
+/// 2.3.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockdevBackupCommand {
+    /// the name of the device which should be copied.
+    pub device: String,
+    /// the name of the backup target device.
+    pub target: String,
+    /// what parts of the disk image should be copied to the destination (all the disk, only the sectors allocated in the topmost image, or only new I/O).
+    pub sync: MirrorSyncMode,
+    /// the maximum speed, in bytes per second. The default is 0, for unlimited.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speed: Option<i64>,
+    /// the action to take on an error on the source, default 'report'.  'stop' and 'enospc' can only be used if the block device supports io-status (see BlockInfo).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_source_error: Option<BlockdevOnError>,
+    /// the action to take on an error on the target, default 'report' (no limitations, since this applies to a different block device than [`device`](BlockdevBackupCommand::device)).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_target_error: Option<BlockdevOnError>,
+}
+
+impl QmpCommand for BlockdevBackupCommand {
+    type Ok = Empty;
+    const NAME: &'static str = "blockdev-backup";
+    const ALLOW_OOB: bool = false;
+    const SUCCESS_RESPONSE: bool = true;
+}
+
+/// Get the named block driver list
+///
+/// # Returns
+///
+/// the list of BlockDeviceInfo
+///
+/// # Since
+///
+/// 2.0.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct QueryNamedBlockNodesCommand {}
+
+impl QmpCommand for QueryNamedBlockNodesCommand {
+    type Ok = Vec<BlockDeviceInfo>;
+    const NAME: &'static str = "query-named-block-nodes";
+    const ALLOW_OOB: bool = false;
+    const SUCCESS_RESPONSE: bool = true;
+}
+
+/// Start mirroring a block device's writes to a new destination.
+///
+/// # Returns
+///
+/// nothing on success
+///
+/// # Errors
+///
+/// If [`device`](DriveMirrorCommand::device) is not a valid block device, DeviceNotFound
+///
+/// # Since
+///
+/// 1.3.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct DriveMirrorCommand {
+    /// the name of the device whose writes should be mirrored.
+    pub device: String,
+    /// the target of the new image. If the file exists, or if it is a device, the existing file/device will be used as the new destination.  If it does not exist, a new file will be created.
+    pub target: String,
+    /// the format of the new destination, default is to probe if [`mode`](DriveMirrorCommand::mode) is 'existing', else the format of the source
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// the new block driver state node name in the graph (Since 2.1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_name: Option<String>,
+    /// with sync=full graph node name to be replaced by the new image when a whole image copy is done. This can be used to repair broken Quorum files. (Since 2.1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replaces: Option<String>,
+    /// what parts of the disk image should be copied to the destination (all the disk, only the sectors allocated in the topmost image, or only new I/O).
+    pub sync: MirrorSyncMode,
+    /// whether and how QEMU should create a new image, default is 'absolute-paths'.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<NewImageMode>,
+    /// the maximum speed, in bytes per second
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speed: Option<i64>,
+    /// granularity of the dirty bitmap, default is 64K if the image format doesn't have clusters, 4K if the clusters are smaller than that, else the cluster size.  Must be a power of 2 between 512 and 64M (since 1.4).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub granularity: Option<u32>,
+    /// maximum amount of data in flight from source to target (since 1.4).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buf_size: Option<i64>,
+    /// the action to take on an error on the source, default 'report'.  'stop' and 'enospc' can only be used if the block device supports io-status (see BlockInfo).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_source_error: Option<BlockdevOnError>,
+    /// the action to take on an error on the target, default 'report' (no limitations, since this applies to a different block device than [`device`](DriveMirrorCommand::device)).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_target_error: Option<BlockdevOnError>,
+}
+
+impl QmpCommand for DriveMirrorCommand {
+    type Ok = Empty;
+    const NAME: &'static str = "drive-mirror";
+    const ALLOW_OOB: bool = false;
+    const SUCCESS_RESPONSE: bool = true;
+}
+
+/// # Since
+///
+/// 2.4.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockDirtyBitmap {
+    /// name of device/node which the bitmap is tracking
+    pub node: String,
+    /// name of the dirty bitmap
+    pub name: String,
+}
+
+/// # Since
+///
+/// 2.4.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockDirtyBitmapAdd {
+    /// name of device/node which the bitmap is tracking
+    pub node: String,
+    /// name of the dirty bitmap
+    pub name: String,
+    /// the bitmap granularity, default is 64k for block-dirty-bitmap-add
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub granularity: Option<u32>,
+}
+
+/// Create a dirty bitmap with a name on the node
+///
+/// # Returns
+///
+/// nothing on success
+///
+/// # Errors
+///
+/// - If [`node`](BlockDirtyBitmapAddCommand::node) is not a valid block device or node, DeviceNotFound
+/// - If [`name`](BlockDirtyBitmapAddCommand::name) is already taken, GenericError with an explanation
+///
+/// # Since
+///
+/// 2.4.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockDirtyBitmapAddCommand {
+    /// name of device/node which the bitmap is tracking
+    pub node: String,
+    /// name of the dirty bitmap
+    pub name: String,
+    /// the bitmap granularity, default is 64k for block-dirty-bitmap-add
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub granularity: Option<u32>,
+}
+
+impl QmpCommand for BlockDirtyBitmapAddCommand {
+    type Ok = Empty;
+    const NAME: &'static str = "block-dirty-bitmap-add";
+    const ALLOW_OOB: bool = false;
+    const SUCCESS_RESPONSE: bool = true;
+}
+
+/// Remove a dirty bitmap on the node
+///
+/// # Returns
+///
+/// nothing on success
+///
+/// # Errors
+///
+/// - If [`node`](BlockDirtyBitmapRemoveCommand::node) is not a valid block device or node, DeviceNotFound
+/// - If [`name`](BlockDirtyBitmapRemoveCommand::name) is not found, GenericError with an explanation if [`name`](BlockDirtyBitmapRemoveCommand::name) is frozen by an operation, GenericError
+///
+/// # Since
+///
+/// 2.4.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockDirtyBitmapRemoveCommand {
+    /// name of device/node which the bitmap is tracking
+    pub node: String,
+    /// name of the dirty bitmap
+    pub name: String,
+}
+
+impl QmpCommand for BlockDirtyBitmapRemoveCommand {
+    type Ok = Empty;
+    const NAME: &'static str = "block-dirty-bitmap-remove";
+    const ALLOW_OOB: bool = false;
+    const SUCCESS_RESPONSE: bool = true;
+}
+
+/// Clear (reset) a dirty bitmap on the device
+///
+/// # Returns
+///
+/// nothing on success
+///
+/// # Errors
+///
+/// - If [`node`](BlockDirtyBitmapClearCommand::node) is not a valid block device, DeviceNotFound
+/// - If [`name`](BlockDirtyBitmapClearCommand::name) is not found, GenericError with an explanation
+///
+/// # Since
+///
+/// 2.4.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockDirtyBitmapClearCommand {
+    /// name of device/node which the bitmap is tracking
+    pub node: String,
+    /// name of the dirty bitmap
+    pub name: String,
+}
+
+impl QmpCommand for BlockDirtyBitmapClearCommand {
+    type Ok = Empty;
+    const NAME: &'static str = "block-dirty-bitmap-clear";
+    const ALLOW_OOB: bool = false;
+    const SUCCESS_RESPONSE: bool = true;
+}
+
+/// Change I/O throttle limits for a block drive.
+///
+/// # Returns
+///
+/// Nothing on success
+///
+/// # Errors
+///
+/// If [`device`](BlockSetIoThrottleCommand::device) is not a valid block device, DeviceNotFound
+///
+/// # Since
+///
+/// 1.1.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockSetIoThrottleCommand {
+    /// The name of the device
+    pub device: String,
+    /// total throughput limit in bytes per second
+    pub bps: i64,
+    /// read throughput limit in bytes per second
+    #[serde(rename = "bps_rd")]
+    pub bps_rd: i64,
+    /// write throughput limit in bytes per second
+    #[serde(rename = "bps_wr")]
+    pub bps_wr: i64,
+    /// total I/O operations per second
+    pub iops: i64,
+    #[serde(rename = "iops_rd")]
+    pub iops_rd: i64,
+    /// write I/O operations per second
+    #[serde(rename = "iops_wr")]
+    pub iops_wr: i64,
+    /// total max in bytes (Since 1.7)
+    #[serde(rename = "bps_max")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bps_max: Option<i64>,
+    /// read max in bytes (Since 1.7)
+    #[serde(rename = "bps_rd_max")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bps_rd_max: Option<i64>,
+    /// write max in bytes (Since 1.7)
+    #[serde(rename = "bps_wr_max")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bps_wr_max: Option<i64>,
+    /// total I/O operations max (Since 1.7)
+    #[serde(rename = "iops_max")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iops_max: Option<i64>,
+    /// read I/O operations max (Since 1.7)
+    #[serde(rename = "iops_rd_max")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iops_rd_max: Option<i64>,
+    /// write I/O operations max (Since 1.7)
+    #[serde(rename = "iops_wr_max")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iops_wr_max: Option<i64>,
+    /// an I/O size in bytes (Since 1.7)
+    #[serde(rename = "iops_size")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iops_size: Option<i64>,
+}
+
+impl QmpCommand for BlockSetIoThrottleCommand {
+    type Ok = Empty;
+    const NAME: &'static str = "block_set_io_throttle";
+    const ALLOW_OOB: bool = false;
+    const SUCCESS_RESPONSE: bool = true;
+}
+
+/// Copy data from a backing file into a block device.
+///
+/// The block streaming operation is performed in the background until the entire
+/// backing file has been copied.  This command returns immediately once streaming
+/// has started.  The status of ongoing block streaming operations can be checked
+/// with query-block-jobs.  The operation can be stopped before it has completed
+/// using the block-job-cancel command.
+///
+/// If a base file is specified then sectors are not copied from that base file and
+/// its backing chain.  When streaming completes the image file will have the base
+/// file as its backing file.  This can be used to stream a subset of the backing
+/// file chain instead of flattening the entire image.
+///
+/// On successful completion the image file is updated to drop the backing file
+/// and the BLOCK_JOB_COMPLETED event is emitted.
+///
+/// If a pathname string is such that it cannot be
+/// resolved by QEMU, that means that subsequent QMP or
+/// HMP commands must use node-names for the image in
+/// question, as filename lookup methods will fail.
+///
+/// If not specified, QEMU will automatically determine
+/// the backing file string to use, or error out if there
+/// is no obvious choice.  Care should be taken when
+/// specifying the string, to specify a valid filename or
+/// protocol.
+/// (Since 2.1)
+///
+/// # Returns
+///
+/// Nothing on success
+///
+/// # Errors
+///
+/// If [`device`](BlockStreamCommand::device) does not exist, DeviceNotFound
+///
+/// # Since
+///
+/// 1.1.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockStreamCommand {
+    /// the device name
+    pub device: String,
+    /// the common backing file name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+    /// The backing file string to write into the active layer. This filename is not validated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backing_file: Option<String>,
+    /// the maximum speed, in bytes per second
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speed: Option<i64>,
+    /// the action to take on an error (default report). 'stop' and 'enospc' can only be used if the block device supports io-status (see BlockInfo).  Since 1.3.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_error: Option<BlockdevOnError>,
+}
+
+impl QmpCommand for BlockStreamCommand {
+    type Ok = Empty;
+    const NAME: &'static str = "block-stream";
+    const ALLOW_OOB: bool = false;
+    const SUCCESS_RESPONSE: bool = true;
+}
+
+/// Set maximum speed for a background block operation.
+///
+/// This command can only be issued when there is an active block job.
+///
+/// Throttling can be disabled by setting the speed to 0.
+///
+/// # Returns
+///
+/// Nothing on success
+///
+/// # Errors
+///
+/// If no background operation is active on this device, DeviceNotActive
+///
+/// # Since
+///
+/// 1.1.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockJobSetSpeedCommand {
+    /// the device name
+    pub device: String,
+    /// the maximum speed, in bytes per second, or 0 for unlimited. Defaults to 0.
+    pub speed: i64,
+}
+
+impl QmpCommand for BlockJobSetSpeedCommand {
+    type Ok = Empty;
+    const NAME: &'static str = "block-job-set-speed";
+    const ALLOW_OOB: bool = false;
+    const SUCCESS_RESPONSE: bool = true;
+}
+
+/// Stop an active background block operation.
+///
+/// This command returns immediately after marking the active background block
+/// operation for cancellation.  It is an error to call this command if no
+/// operation is in progress.
+///
+/// The operation will cancel as soon as possible and then emit the
+/// BLOCK_JOB_CANCELLED event.  Before that happens the job is still visible when
+/// enumerated using query-block-jobs.
+///
+/// For streaming, the image file retains its backing file unless the streaming
+/// operation happens to complete just as it is being cancelled.  A new streaming
+/// operation can be started at a later time to finish copying all data from the
+/// backing file.
+///
+/// # Returns
+///
+/// Nothing on success
+///
+/// # Errors
+///
+/// If no background operation is active on this device, DeviceNotActive
+///
+/// # Since
+///
+/// 1.1.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockJobCancelCommand {
+    /// the device name
+    pub device: String,
+    /// whether to allow cancellation of a paused job (default false).  Since 1.3.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub force: Option<bool>,
+}
+
+impl QmpCommand for BlockJobCancelCommand {
+    type Ok = Empty;
+    const NAME: &'static str = "block-job-cancel";
+    const ALLOW_OOB: bool = false;
+    const SUCCESS_RESPONSE: bool = true;
+}
+
+/// Pause an active background block operation.
+///
+/// This command returns immediately after marking the active background block
+/// operation for pausing.  It is an error to call this command if no
+/// operation is in progress.  Pausing an already paused job has no cumulative
+/// effect; a single block-job-resume command will resume the job.
+///
+/// The operation will pause as soon as possible.  No event is emitted when
+/// the operation is actually paused.  Cancelling a paused job automatically
+/// resumes it.
+///
+/// # Returns
+///
+/// Nothing on success
+///
+/// # Errors
+///
+/// If no background operation is active on this device, DeviceNotActive
+///
+/// # Since
+///
+/// 1.3.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockJobPauseCommand {
+    /// the device name
+    pub device: String,
+}
+
+impl QmpCommand for BlockJobPauseCommand {
+    type Ok = Empty;
+    const NAME: &'static str = "block-job-pause";
+    const ALLOW_OOB: bool = false;
+    const SUCCESS_RESPONSE: bool = true;
+}
+
+/// Resume an active background block operation.
+///
+/// This command returns immediately after resuming a paused background block
+/// operation.  It is an error to call this command if no operation is in
+/// progress.  Resuming an already running job is not an error.
+///
+/// This command also clears the error status of the job.
+///
+/// # Returns
+///
+/// Nothing on success
+///
+/// # Errors
+///
+/// If no background operation is active on this device, DeviceNotActive
+///
+/// # Since
+///
+/// 1.3.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockJobResumeCommand {
+    /// the device name
+    pub device: String,
+}
+
+impl QmpCommand for BlockJobResumeCommand {
+    type Ok = Empty;
+    const NAME: &'static str = "block-job-resume";
+    const ALLOW_OOB: bool = false;
+    const SUCCESS_RESPONSE: bool = true;
+}
+
+/// Manually trigger completion of an active background block operation.  This
+/// is supported for drive mirroring, where it also switches the device to
+/// write to the target path only.  The ability to complete is signaled with
+/// a BLOCK_JOB_READY event.
+///
+/// This command completes an active background block operation synchronously.
+/// The ordering of this command's return with the BLOCK_JOB_COMPLETED event
+/// is not defined.  Note that if an I/O error occurs during the processing of
+/// this command: 1) the command itself will fail; 2) the error will be processed
+/// according to the rerror/werror arguments that were specified when starting
+/// the operation.
+///
+/// A cancelled or paused job cannot be completed.
+///
+/// # Returns
+///
+/// Nothing on success
+///
+/// # Errors
+///
+/// If no background operation is active on this device, DeviceNotActive
+///
+/// # Since
+///
+/// 1.3.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockJobCompleteCommand {
+    /// the device name
+    pub device: String,
+}
+
+impl QmpCommand for BlockJobCompleteCommand {
+    type Ok = Empty;
+    const NAME: &'static str = "block-job-complete";
+    const ALLOW_OOB: bool = false;
+    const SUCCESS_RESPONSE: bool = true;
+}
+
+/// Determines how to handle discard requests.
+///
+/// # Since
+///
+/// 1.7.0
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BlockdevDiscardOptions {
+    /// Ignore the request
+    Ignore,
+    /// Forward as an unmap request
+    Unmap,
+}
+
+/// Selects the AIO backend to handle I/O requests
+///
+/// # Since
+///
+/// 1.7.0
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BlockdevAioOptions {
+    /// Use qemu's thread pool
+    Threads,
+    /// Use native AIO backend (only Linux and Windows)
+    Native,
+}
+
+/// Includes cache-related options for block devices
+///
+/// # Since
+///
+/// 1.7.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockdevCacheOptions {
+    /// enables writeback mode for any caches (default: true)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub writeback: Option<bool>,
+    /// enables use of O_DIRECT (bypass the host page cache; default: false)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub direct: Option<bool>,
+    /// ignore any flush requests for the device (default: false)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub no_flush: Option<bool>,
+}
+
+/// Drivers that are supported in block device operations.
+///
+/// # Since
+///
+/// 2.0.0
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BlockdevDriver {
+    Archipelago,
+    Blkdebug,
+    Blkverify,
+    Bochs,
+    Cloop,
+    Dmg,
+    File,
+    Ftp,
+    Ftps,
+    #[serde(rename = "host_cdrom")]
+    HostCdrom,
+    #[serde(rename = "host_device")]
+    HostDevice,
+    /// deprecated since 2.3
+    #[serde(rename = "host_floppy")]
+    HostFloppy,
+    Http,
+    Https,
+    NullAio,
+    NullCo,
+    Parallels,
+    Qcow,
+    Qcow2,
+    Qed,
+    Quorum,
+    Raw,
+    Tftp,
+    Vdi,
+    Vhdx,
+    Vmdk,
+    Vpc,
+    Vvfat,
+}
+
+/// Options that are available for all block devices, independent of the block
+/// driver.
+///
+/// # Since
+///
+/// 1.7.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockdevOptionsBase {
+    /// block driver name
+    pub driver: BlockdevDriver,
+    /// id by which the new block device can be referred to. This is a required option on the top level of blockdev-add, and currently not allowed on any other level.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// the name of a block driver state node (Since 2.0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_name: Option<String>,
+    /// discard-related options (default: ignore)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub discard: Option<BlockdevDiscardOptions>,
+    /// cache-related options
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache: Option<BlockdevCacheOptions>,
+    /// AIO backend (default: threads)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aio: Option<BlockdevAioOptions>,
+    /// how to handle read errors on the device (default: report)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rerror: Option<BlockdevOnError>,
+    /// how to handle write errors on the device (default: enospc)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub werror: Option<BlockdevOnError>,
+    /// whether the block device should be read-only (default: false)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub read_only: Option<bool>,
+    /// detect and optimize zero writes (Since 2.1) (default: off)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detect_zeroes: Option<BlockdevDetectZeroesOptions>,
+}
+
+/// Driver specific block device options for the file backend and similar
+/// protocols.
+///
+/// # Since
+///
+/// 1.7.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockdevOptionsFile {
+    /// path to the image file
+    pub filename: String,
+}
+
+/// Driver specific block device options for the null backend.
+///
+/// # Since
+///
+/// 2.2.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockdevOptionsNull {
+    /// size of the device in bytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<i64>,
+    /// emulated latency (in nanoseconds) in processing requests. Default to zero which completes requests immediately. (Since 2.4)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_ns: Option<u64>,
+}
+
+/// Driver specific block device options for the vvfat protocol.
+///
+/// # Since
+///
+/// 1.7.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockdevOptionsVVFAT {
+    /// directory to be exported as FAT image
+    pub dir: String,
+    /// FAT type: 12, 16 or 32
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fat_type: Option<i64>,
+    /// whether to export a floppy image (true) or partitioned hard disk (false; default)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub floppy: Option<bool>,
+    /// whether to allow write operations (default: false)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rw: Option<bool>,
+}
+
+/// Driver specific block device options for Archipelago.
+///
+/// # Since
+///
+/// 2.2.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockdevOptionsArchipelago {
+    /// Name of the Archipelago volume image
+    pub volume: String,
+    /// The port number on which mapperd is listening. This is optional and if not specified, QEMU will make Archipelago use the default port (1001).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mport: Option<i64>,
+    /// The port number on which vlmcd is listening. This is optional and if not specified, QEMU will make Archipelago use the default port (501).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vport: Option<i64>,
+    /// The name of the shared memory segment Archipelago stack is using. This is optional and if not specified, QEMU will make Archipelago use the default value, 'archipelago'.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub segment: Option<String>,
+}
+
+/// Trigger events supported by blkdebug.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BlkdebugEvent {
+    #[serde(rename = "l1_update")]
+    L1Update,
+    #[serde(rename = "l1_grow.alloc_table")]
+    L1GrowAllocTable,
+    #[serde(rename = "l1_grow.write_table")]
+    L1GrowWriteTable,
+    #[serde(rename = "l1_grow.activate_table")]
+    L1GrowActivateTable,
+    #[serde(rename = "l2_load")]
+    L2Load,
+    #[serde(rename = "l2_update")]
+    L2Update,
+    #[serde(rename = "l2_update_compressed")]
+    L2UpdateCompressed,
+    #[serde(rename = "l2_alloc.cow_read")]
+    L2AllocCowRead,
+    #[serde(rename = "l2_alloc.write")]
+    L2AllocWrite,
+    #[serde(rename = "read_aio")]
+    ReadAio,
+    #[serde(rename = "read_backing_aio")]
+    ReadBackingAio,
+    #[serde(rename = "read_compressed")]
+    ReadCompressed,
+    #[serde(rename = "write_aio")]
+    WriteAio,
+    #[serde(rename = "write_compressed")]
+    WriteCompressed,
+    #[serde(rename = "vmstate_load")]
+    VmstateLoad,
+    #[serde(rename = "vmstate_save")]
+    VmstateSave,
+    #[serde(rename = "cow_read")]
+    CowRead,
+    #[serde(rename = "cow_write")]
+    CowWrite,
+    #[serde(rename = "reftable_load")]
+    ReftableLoad,
+    #[serde(rename = "reftable_grow")]
+    ReftableGrow,
+    #[serde(rename = "reftable_update")]
+    ReftableUpdate,
+    #[serde(rename = "refblock_load")]
+    RefblockLoad,
+    #[serde(rename = "refblock_update")]
+    RefblockUpdate,
+    #[serde(rename = "refblock_update_part")]
+    RefblockUpdatePart,
+    #[serde(rename = "refblock_alloc")]
+    RefblockAlloc,
+    #[serde(rename = "refblock_alloc.hookup")]
+    RefblockAllocHookup,
+    #[serde(rename = "refblock_alloc.write")]
+    RefblockAllocWrite,
+    #[serde(rename = "refblock_alloc.write_blocks")]
+    RefblockAllocWriteBlocks,
+    #[serde(rename = "refblock_alloc.write_table")]
+    RefblockAllocWriteTable,
+    #[serde(rename = "refblock_alloc.switch_table")]
+    RefblockAllocSwitchTable,
+    #[serde(rename = "cluster_alloc")]
+    ClusterAlloc,
+    #[serde(rename = "cluster_alloc_bytes")]
+    ClusterAllocBytes,
+    #[serde(rename = "cluster_free")]
+    ClusterFree,
+    #[serde(rename = "flush_to_os")]
+    FlushToOs,
+    #[serde(rename = "flush_to_disk")]
+    FlushToDisk,
+    #[serde(rename = "pwritev_rmw.head")]
+    PwritevRmwHead,
+    #[serde(rename = "pwritev_rmw.after_head")]
+    PwritevRmwAfterHead,
+    #[serde(rename = "pwritev_rmw.tail")]
+    PwritevRmwTail,
+    #[serde(rename = "pwritev_rmw.after_tail")]
+    PwritevRmwAfterTail,
+    Pwritev,
+    #[serde(rename = "pwritev_zero")]
+    PwritevZero,
+    #[serde(rename = "pwritev_done")]
+    PwritevDone,
+    #[serde(rename = "empty_image_prepare")]
+    EmptyImagePrepare,
+}
+
+/// Describes a single error injection for blkdebug.
+///
+/// # Since
+///
+/// 2.0.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlkdebugInjectErrorOptions {
+    /// trigger event
+    pub event: BlkdebugEvent,
+    /// the state identifier blkdebug needs to be in to actually trigger the event; defaults to "any"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<i64>,
+    /// error identifier (errno) to be returned; defaults to EIO
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub errno: Option<i64>,
+    /// specifies the sector index which has to be affected in order to actually trigger the event; defaults to "any sector"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sector: Option<i64>,
+    /// disables further events after this one has been triggered; defaults to false
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub once: Option<bool>,
+    /// fail immediately; defaults to false
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub immediately: Option<bool>,
+}
+
+/// Describes a single state-change event for blkdebug.
+///
+/// # Since
+///
+/// 2.0.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlkdebugSetStateOptions {
+    /// trigger event
+    pub event: BlkdebugEvent,
+    /// the current state identifier blkdebug needs to be in; defaults to "any"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<i64>,
+    /// the state identifier blkdebug is supposed to assume if this event is triggered
+    #[serde(rename = "new_state")]
+    pub new_state: i64,
+}
+
+/// General overlap check modes.
+///
+/// # Since
+///
+/// 2.2.0
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Qcow2OverlapCheckMode {
+    /// Do not perform any checks
+    None,
+    /// Perform only checks which can be done in constant time and without reading anything from disk
+    Constant,
+    /// Perform only checks which can be done without reading anything from disk
+    Cached,
+    /// Perform all available overlap checks
+    All,
+}
+
+/// Structure of flags for each metadata structure. Setting a field to 'true'
+/// makes qemu guard that structure against unintended overwriting. The default
+/// value is chosen according to the template given.
+///
+/// # Since
+///
+/// 2.2.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Qcow2OverlapCheckFlags {
+    /// Specifies a template mode which can be adjusted using the other flags, defaults to 'cached'
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<Qcow2OverlapCheckMode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub main_header: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_l1: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_l2: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refcount_table: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refcount_block: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot_table: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inactive_l1: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inactive_l2: Option<bool>,
+}
+
+/// Specifies which metadata structures should be guarded against unintended
+/// overwriting.
+///
+/// # Since
+///
+/// 2.2.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Qcow2OverlapChecks {
+    /// named mode which chooses a specific set of flags
+    Mode(Qcow2OverlapCheckMode),
+    /// set of flags for separate specification of each metadata structure type
+    Flags(Qcow2OverlapCheckFlags),
+}
+
+/// An enumeration of quorum read patterns.
+///
+/// # Since
+///
+/// 2.2.0
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum QuorumReadPattern {
+    /// read all the children and do a quorum vote on reads
+    Quorum,
+    /// read only from the first child that has not failed
+    Fifo,
+}
+
+/// Driver specific block device options for image format that have no option
+/// besides their data source.
+///
+/// # Since
+///
+/// 1.7.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockdevOptionsGenericFormat {
+    /// reference to or definition of the data source block device
+    pub file: Box<BlockdevRef>,
+}
+
+/// Reference to a block device.
+///
+/// # Since
+///
+/// 1.7.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum BlockdevRef {
+    /// references the ID of an existing block device. An empty string means that no block device should be referenced.
+    Reference(String),
+    /// defines a new block device inline
+    Definition(Box<BlockdevOptions>),
+}
+
+/// Options for creating a block device.
+///
+/// # Since
+///
+/// 1.7.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockdevOptions {
+    /// id by which the new block device can be referred to. This is a required option on the top level of blockdev-add, and currently not allowed on any other level.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// the name of a block driver state node (Since 2.0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_name: Option<String>,
+    /// discard-related options (default: ignore)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub discard: Option<BlockdevDiscardOptions>,
+    /// cache-related options
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache: Option<BlockdevCacheOptions>,
+    /// AIO backend (default: threads)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aio: Option<BlockdevAioOptions>,
+    /// how to handle read errors on the device (default: report)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rerror: Option<BlockdevOnError>,
+    /// how to handle write errors on the device (default: enospc)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub werror: Option<BlockdevOnError>,
+    /// whether the block device should be read-only (default: false)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub read_only: Option<bool>,
+    /// detect and optimize zero writes (Since 2.1) (default: off)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detect_zeroes: Option<BlockdevDetectZeroesOptions>,
+    /// block driver name
+    #[serde(flatten)]
+    pub driver: BlockdevOptionsBranch,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "driver", rename_all = "kebab-case")]
+pub enum BlockdevOptionsBranch {
+    Archipelago(BlockdevOptionsArchipelago),
+    Blkdebug(Box<BlockdevOptionsBlkdebug>),
+    Blkverify(Box<BlockdevOptionsBlkverify>),
+    Bochs(Box<BlockdevOptionsGenericFormat>),
+    Cloop(Box<BlockdevOptionsGenericFormat>),
+    Dmg(Box<BlockdevOptionsGenericFormat>),
+    File(BlockdevOptionsFile),
+    Ftp(BlockdevOptionsFile),
+    Ftps(BlockdevOptionsFile),
+    #[serde(rename = "host_cdrom")]
+    HostCdrom(BlockdevOptionsFile),
+    #[serde(rename = "host_device")]
+    HostDevice(BlockdevOptionsFile),
+    /// deprecated since 2.3
+    #[serde(rename = "host_floppy")]
+    HostFloppy(BlockdevOptionsFile),
+    Http(BlockdevOptionsFile),
+    Https(BlockdevOptionsFile),
+    NullAio(BlockdevOptionsNull),
+    NullCo(BlockdevOptionsNull),
+    Parallels(Box<BlockdevOptionsGenericFormat>),
+    Qcow(Box<BlockdevOptionsGenericCOWFormat>),
+    Qcow2(Box<BlockdevOptionsQcow2>),
+    Qed(Box<BlockdevOptionsGenericCOWFormat>),
+    Quorum(BlockdevOptionsQuorum),
+    Raw(Box<BlockdevOptionsGenericFormat>),
+    Tftp(BlockdevOptionsFile),
+    Vdi(Box<BlockdevOptionsGenericFormat>),
+    Vhdx(Box<BlockdevOptionsGenericFormat>),
+    Vmdk(Box<BlockdevOptionsGenericCOWFormat>),
+    Vpc(Box<BlockdevOptionsGenericFormat>),
+    Vvfat(BlockdevOptionsVVFAT),
+}
+
+/// Driver specific block device options for blkdebug.
+///
+/// # Since
+///
+/// 2.0.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockdevOptionsBlkdebug {
+    /// underlying raw block device (or image file)
+    pub image: Box<BlockdevRef>,
+    /// filename of the configuration file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<String>,
+    /// required alignment for requests in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub align: Option<i64>,
+    /// array of error injection descriptions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inject_error: Option<Vec<BlkdebugInjectErrorOptions>>,
+    /// array of state-change descriptions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub set_state: Option<Vec<BlkdebugSetStateOptions>>,
+}
+
+/// Driver specific block device options for blkverify.
+///
+/// # Since
+///
+/// 2.0.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockdevOptionsBlkverify {
+    /// block device to be tested
+    pub test: Box<BlockdevRef>,
+    /// raw image used for verification
+    pub raw: Box<BlockdevRef>,
+}
+
+/// Driver specific block device options for qcow2.
+///
+/// # Since
+///
+/// 1.7.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockdevOptionsQcow2 {
+    /// reference to or definition of the data source block device
+    pub file: Box<BlockdevRef>,
+    /// reference to or definition of the backing file block device (if missing, taken from the image file content). It is allowed to pass an empty string here in order to disable the default backing file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backing: Option<Box<BlockdevRef>>,
+    /// whether to enable the lazy refcounts feature (default is taken from the image file)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lazy_refcounts: Option<bool>,
+    /// whether discard requests to the qcow2 device should be forwarded to the data source
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pass_discard_request: Option<bool>,
+    /// whether discard requests for the data source should be issued when a snapshot operation (e.g. deleting a snapshot) frees clusters in the qcow2 file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pass_discard_snapshot: Option<bool>,
+    /// whether discard requests for the data source should be issued on other occasions where a cluster gets freed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pass_discard_other: Option<bool>,
+    /// which overlap checks to perform for writes to the image, defaults to 'cached' (since 2.2)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overlap_check: Option<Qcow2OverlapChecks>,
+    /// the maximum total size of the L2 table and refcount block caches in bytes (since 2.2)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_size: Option<i64>,
+    /// the maximum size of the L2 table cache in bytes (since 2.2)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub l2_cache_size: Option<i64>,
+    /// the maximum size of the refcount block cache in bytes (since 2.2)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refcount_cache_size: Option<i64>,
+}
+
+/// Driver specific block device options for image format that have no option
+/// besides their data source and an optional backing file.
+///
+/// # Since
+///
+/// 1.7.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockdevOptionsGenericCOWFormat {
+    /// reference to or definition of the data source block device
+    pub file: Box<BlockdevRef>,
+    /// reference to or definition of the backing file block device (if missing, taken from the image file content). It is allowed to pass an empty string here in order to disable the default backing file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backing: Option<Box<BlockdevRef>>,
+}
+
+/// Driver specific block device options for Quorum
+///
+/// # Since
+///
+/// 2.0.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockdevOptionsQuorum {
+    /// true if the driver must print content mismatch set to false by default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blkverify: Option<bool>,
+    /// the children block devices to use
+    pub children: Vec<BlockdevRef>,
+    /// the vote limit under which a read will fail
+    pub vote_threshold: i64,
+    /// rewrite corrupted data when quorum is reached (Since 2.1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rewrite_corrupted: Option<bool>,
+    /// choose read pattern and set to quorum by default (Since 2.2)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub read_pattern: Option<QuorumReadPattern>,
+}
+
+/// Creates a new block device.
+///
+/// This command is still a work in progress.  It doesn't support all
+/// block drivers, it lacks a matching blockdev-del, and more.  Stay
+/// away from it unless you want to help with its development.
+///
+/// # Since
+///
+/// 1.7.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockdevAddCommand {
+    /// block device options for the new device
+    pub options: BlockdevOptions,
+}
+
+impl QmpCommand for BlockdevAddCommand {
+    type Ok = Empty;
+    const NAME: &'static str = "blockdev-add";
+    const ALLOW_OOB: bool = false;
+    const SUCCESS_RESPONSE: bool = true;
+}
+
+/// An enumeration of action that has been taken when a DISK I/O occurs
+///
+/// # Since
+///
+/// 2.1.0
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BlockErrorAction {
+    /// error has been ignored
+    Ignore,
+    /// error has been reported to the device
+    Report,
+    /// error caused VM to be stopped
+    Stop,
+}
+
+/// The data of the BLOCK_IMAGE_CORRUPTED event
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockImageCorruptedEvent {
+    /// device name. This is always present for compatibility reasons, but it can be empty ("") if the image does not have a device name associated.
+    pub device: String,
+    /// node name (Since: 2.4)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_name: Option<String>,
+    /// informative message for human consumption, such as the kind of corruption being detected. It should not be parsed by machine as it is not guaranteed to be stable
+    pub msg: String,
+    /// if the corruption resulted from an image access, this is the host's access offset into the image
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<i64>,
+    /// if the corruption resulted from an image access, this is the access size
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<i64>,
+    pub fatal: bool,
+}
+
+/// The data of the BLOCK_IO_ERROR event
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockIoErrorEvent {
+    /// device name
+    pub device: String,
+    /// I/O operation
+    pub operation: serde_json::Value,
+    /// action that has been taken
+    pub action: BlockErrorAction,
+    /// true if I/O error was caused due to a no-space condition. This key is only present if query-block's io-status is present, please see query-block documentation for more information (since: 2.2)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nospace: Option<bool>,
+    /// human readable string describing the error cause. (This field is a debugging aid for humans, it should not be parsed by applications) (since: 2.2)
+    pub reason: String,
+}
+
+/// The data of the BLOCK_JOB_COMPLETED event
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockJobCompletedEvent {
+    /// job type
+    #[serde(rename = "type")]
+    pub qemu_type: BlockJobType,
+    /// device name
+    pub device: String,
+    /// maximum progress value
+    pub len: i64,
+    /// current progress value. On success this is equal to len. On failure this is less than len
+    pub offset: i64,
+    /// rate limit, bytes per second
+    pub speed: i64,
+    /// error message. Only present on failure. This field contains a human-readable error message. There are no semantics other than that streaming has failed and clients should not try to interpret the error string
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The data of the BLOCK_JOB_CANCELLED event
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockJobCancelledEvent {
+    /// job type
+    #[serde(rename = "type")]
+    pub qemu_type: BlockJobType,
+    /// device name
+    pub device: String,
+    /// maximum progress value
+    pub len: i64,
+    /// current progress value. On success this is equal to len. On failure this is less than len
+    pub offset: i64,
+    /// rate limit, bytes per second
+    pub speed: i64,
+}
+
+/// The data of the BLOCK_JOB_ERROR event
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockJobErrorEvent {
+    /// device name
+    pub device: String,
+    /// I/O operation
+    pub operation: serde_json::Value,
+    /// action that has been taken
+    pub action: BlockErrorAction,
+}
+
+/// The data of the BLOCK_JOB_READY event
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockJobReadyEvent {
+    /// job type
+    #[serde(rename = "type")]
+    pub qemu_type: BlockJobType,
+    /// device name
+    pub device: String,
+    /// maximum progress value
+    pub len: i64,
+    /// current progress value. On success this is equal to len. On failure this is less than len
+    pub offset: i64,
+    /// rate limit, bytes per second
+    pub speed: i64,
+}
+
+/// Preallocation mode of QEMU image file
+///
+/// # Since
+///
+/// 2.2.0
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PreallocMode {
+    /// no preallocation
+    Off,
+    /// preallocate only for metadata
+    Metadata,
+    /// like [`full`](PreallocMode::Full) preallocation but allocate disk space by posix_fallocate() rather than writing zeros.
+    Falloc,
+    /// preallocate all data by writing zeros to device to ensure disk space is really available. [`full`](PreallocMode::Full) preallocation also sets up metadata correctly.
+    Full,
+}
+
+/// The data of the BLOCK_WRITE_THRESHOLD event
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockWriteThresholdEvent {
+    /// graph node name on which the threshold was exceeded.
+    pub node_name: String,
+    /// amount of data which exceeded the threshold, in bytes.
+    pub amount_exceeded: u64,
+    /// last configured threshold, in bytes.
+    pub write_threshold: u64,
+}
+
+/// Change the write threshold for a block drive. An event will be delivered
+/// if a write to this block drive crosses the configured threshold.
+/// This is useful to transparently resize thin-provisioned drives without
+/// the guest OS noticing.
+///
+/// # Since
+///
+/// 2.3.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlockSetWriteThresholdCommand {
+    /// graph node name on which the threshold must be set.
+    pub node_name: String,
+    /// configured threshold for the block device, bytes. Use 0 to disable the threshold.
+    pub write_threshold: u64,
+}
+
+impl QmpCommand for BlockSetWriteThresholdCommand {
+    type Ok = Empty;
+    const NAME: &'static str = "block-set-write-threshold";
+    const ALLOW_OOB: bool = false;
+    const SUCCESS_RESPONSE: bool = true;
+}
+
+/// When QEMU emitted an event
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Timestamp {
+    pub seconds: i64,
+    pub microseconds: i64,
+}
+
+/// An event from QEMU, as sent on the wire.  Events this schema
+/// doesn't know about deserialize to `Unknown`, without their data.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event")]
+pub enum QmpEvent {
+    /// Emitted when a corruption has been detected in a disk image
+    ///
+    /// fatal: if set, the image is marked corrupt and therefore unusable after this
+    /// event and must be repaired (Since 2.2; before, every
+    /// BLOCK_IMAGE_CORRUPTED event was fatal)
+    ///
+    /// # Since
+    ///
+    /// 1.7.0
+    #[serde(rename = "BLOCK_IMAGE_CORRUPTED")]
+    BlockImageCorrupted {
+        data: BlockImageCorruptedEvent,
+        timestamp: Timestamp,
+    },
+    /// Emitted when a disk I/O error occurs
+    ///
+    /// # Since
+    ///
+    /// 0.13.0
+    #[serde(rename = "BLOCK_IO_ERROR")]
+    BlockIoError {
+        data: BlockIoErrorEvent,
+        timestamp: Timestamp,
+    },
+    /// Emitted when a block job has completed
+    ///
+    /// # Since
+    ///
+    /// 1.1.0
+    #[serde(rename = "BLOCK_JOB_COMPLETED")]
+    BlockJobCompleted {
+        data: BlockJobCompletedEvent,
+        timestamp: Timestamp,
+    },
+    /// Emitted when a block job has been cancelled
+    ///
+    /// # Since
+    ///
+    /// 1.1.0
+    #[serde(rename = "BLOCK_JOB_CANCELLED")]
+    BlockJobCancelled {
+        data: BlockJobCancelledEvent,
+        timestamp: Timestamp,
+    },
+    /// Emitted when a block job encounters an error
+    ///
+    /// # Since
+    ///
+    /// 1.3.0
+    #[serde(rename = "BLOCK_JOB_ERROR")]
+    BlockJobError {
+        data: BlockJobErrorEvent,
+        timestamp: Timestamp,
+    },
+    /// Emitted when a block job is ready to complete
+    ///
+    /// # Since
+    ///
+    /// 1.3.0
+    #[serde(rename = "BLOCK_JOB_READY")]
+    BlockJobReady {
+        data: BlockJobReadyEvent,
+        timestamp: Timestamp,
+    },
+    /// Emitted when writes on block device reaches or exceeds the
+    /// configured write threshold. For thin-provisioned devices, this
+    /// means the device should be extended to avoid pausing for
+    /// disk exhaustion.
+    /// The event is one shot. Once triggered, it needs to be
+    /// re-registered with another block-set-threshold command.
+    ///
+    /// # Since
+    ///
+    /// 2.3.0
+    #[serde(rename = "BLOCK_WRITE_THRESHOLD")]
+    BlockWriteThreshold {
+        data: BlockWriteThresholdEvent,
+        timestamp: Timestamp,
+    },
+    #[serde(other)]
+    Unknown,
+}
@@ -0,0 +1,46 @@
+extern crate serde;
+#[macro_use]
+extern crate serde_json;
+
+// Generated from tests/block-core.json, test_generated_block_core in
+// test.rs keeps it up to date
+include!("generated/block_core.rs");
+
+#[test]
+fn test_simple_union_roundtrip() {
+    let reply = json!({
+        "type": "qcow2",
+        "data": { "compat": "1.1", "lazy-refcounts": false, "refcount-bits": 16 }
+    });
+    let info: ImageInfoSpecific = serde_json::from_value(reply.clone()).unwrap();
+    match info {
+        ImageInfoSpecific::Qcow2(ref q) => {
+            assert_eq!(q.compat, "1.1");
+            assert_eq!(q.refcount_bits, 16);
+        }
+        ref other => panic!("Not qcow2: {:?}", other),
+    }
+    assert_eq!(serde_json::to_value(&info).unwrap(), reply);
+}
+
+#[test]
+fn test_flat_union_roundtrip() {
+    let options = json!({
+        "driver": "qcow2",
+        "node-name": "disk0",
+        "file": { "driver": "file", "filename": "/tmp/disk.qcow2" }
+    });
+    let blockdev: BlockdevOptions = serde_json::from_value(options.clone()).unwrap();
+    assert_eq!(blockdev.node_name, Some("disk0".to_string()));
+    match blockdev.driver {
+        BlockdevOptionsBranch::Qcow2(ref q) => match *q.file {
+            BlockdevRef::Definition(ref file) => match file.driver {
+                BlockdevOptionsBranch::File(ref f) => assert_eq!(f.filename, "/tmp/disk.qcow2"),
+                ref other => panic!("Not a file: {:?}", other),
+            },
+            ref other => panic!("Not a definition: {:?}", other),
+        },
+        ref other => panic!("Not qcow2: {:?}", other),
+    }
+    assert_eq!(serde_json::to_value(&blockdev).unwrap(), options);
+}
@@ -123,3 +123,168 @@ fn test_event() {
     }
     // println!("QAPI Result: {:?}", result);
 }
+
+#[test]
+fn test_block_core_recursion() {
+    let mut f = File::open("tests/block-core.json").unwrap();
+    let mut buf = String::new();
+    f.read_to_string(&mut buf).unwrap();
+
+    let schema = parse_qapi::Schema::parse(&buf).unwrap();
+    let graph = parse_qapi::DependencyGraph::new(&schema);
+    // BlockdevOptions refers back to itself through the BlockdevRef alternate
+    assert!(graph.is_recursive("BlockdevOptions"));
+    assert!(graph.is_recursive("BlockdevRef"));
+    assert!(graph.needs_box("BlockdevOptionsGenericFormat", "BlockdevRef"));
+    assert!(!graph.is_recursive("SnapshotInfo"));
+}
+
+#[test]
+fn test_qapi_users() {
+    let mut f = File::open("tests/qapi.json").unwrap();
+    let mut buf = String::new();
+    f.read_to_string(&mut buf).unwrap();
+
+    let schema = parse_qapi::Schema::parse(&buf).unwrap();
+    assert_eq!(
+        schema.commands_returning("VncClientInfo"),
+        vec!["query-vnc", "query-vnc-servers"]
+    );
+    assert!(
+        schema
+            .reachable_from("query-vnc")
+            .contains(&"VncBasicInfo".to_string())
+    );
+    assert!(schema.users_of("VncBasicInfo").contains(&"query-vnc".to_string()));
+}
+
+#[test]
+fn test_prune_block_core() {
+    let schema = parse_qapi::Schema::load("tests/block-core.json").unwrap();
+    let pruned = schema.prune(&["query-block-jobs"]).unwrap();
+    assert!(pruned.get("query-block-jobs").is_some());
+    assert!(pruned.get("BlockJobInfo").is_some());
+    assert!(pruned.get("BlockdevOptions").is_none());
+    assert!(pruned.definitions().len() < schema.definitions().len());
+}
+
+#[test]
+fn test_qapi_since() {
+    let schema = parse_qapi::Schema::load("tests/block-core.json").unwrap();
+    let index = parse_qapi::SinceIndex::new(&schema);
+    let v = |major, minor| parse_qapi::QemuVersion::new(major, minor, 0);
+    assert_eq!(index.since("SnapshotInfo"), Some(v(1, 3)));
+    assert_eq!(index.minimum_version("ImageInfo", "backing-image"), Some(v(1, 6)));
+    let added = index.added_between(v(1, 7), v(2, 5));
+    assert!(!added.is_empty());
+    assert!(!added.contains(&"SnapshotInfo"));
+}
+
+#[test]
+fn test_block_core_introspection() {
+    let schema = parse_qapi::Schema::load("tests/block-core.json").unwrap();
+    let pruned = schema
+        .prune(&["query-block-jobs", "query-named-block-nodes", "BLOCK_JOB_COMPLETED"])
+        .unwrap();
+    let introspection = pruned.introspect(true).unwrap();
+    let loaded = parse_qapi::Schema::from_introspection(&introspection).unwrap();
+    assert_eq!(loaded.introspect(true).unwrap(), introspection);
+    assert!(loaded.get("query-named-block-nodes").is_some());
+}
+
+#[test]
+fn test_syntax_tree_round_trip() {
+    for file in &["qapi", "block-core", "block", "common", "event", "trace"] {
+        let mut input = String::new();
+        File::open(format!("tests/{}.json", file))
+            .unwrap()
+            .read_to_string(&mut input)
+            .unwrap();
+        let tree = parse_qapi::SyntaxTree::parse(&input).unwrap();
+        assert_eq!(tree.to_string(), input);
+        let schema = tree.to_schema().unwrap();
+        assert_eq!(schema.sections.len(), tree.definitions.len());
+    }
+}
+
+#[test]
+fn test_format_fixtures() {
+    for file in &["qapi", "block-core", "block", "common", "event", "trace"] {
+        let mut input = String::new();
+        File::open(format!("tests/{}.json", file))
+            .unwrap()
+            .read_to_string(&mut input)
+            .unwrap();
+        let formatted = parse_qapi::format_source(&input).unwrap();
+        assert_eq!(parse_qapi::format_source(&formatted).unwrap(), formatted);
+        let before = parse_qapi::SyntaxTree::parse(&input).unwrap().to_schema().unwrap();
+        let after = parse_qapi::SyntaxTree::parse(&formatted).unwrap().to_schema().unwrap();
+        assert_eq!(before.definitions(), after.definitions());
+    }
+    let unformatted = parse_qapi::check_format(&["tests/block-core.json"]).unwrap();
+    assert_eq!(unformatted.len(), 1);
+}
+
+#[test]
+fn test_block_ir_round_trip() {
+    let schema = parse_qapi::Schema::load("tests/block.json").unwrap();
+    let ir = schema.to_ir();
+    assert_eq!(ir["definitions"][0]["module"], "common.json");
+    let loaded = parse_qapi::Schema::from_ir(&ir).unwrap();
+    assert_eq!(loaded.to_ir(), ir);
+}
+
+#[test]
+fn test_block_to_rust() {
+    let schema = parse_qapi::Schema::load("tests/block.json").unwrap();
+    // SocketAddress and a few others live in files that aren't part of the
+    // fixtures
+    assert!(schema.to_rust().unwrap_err().contains("SocketAddress is not defined"));
+    let mut types = parse_qapi::TypeMap::default();
+    for (_, dependency) in parse_qapi::DependencyGraph::new(&schema).unresolved() {
+        types.insert(&dependency.name, "serde_json::Value").unwrap();
+    }
+    let rust = schema.to_rust_with(&types).unwrap();
+    assert!(rust.starts_with("use serde::de::DeserializeOwned;\nuse serde::{Deserialize, Serialize};\n\n"));
+    assert!(rust.contains(
+        "\npub struct DriveBackupCommand {\n    /// the name of the device which should be copied.\n    pub device: String,\n"
+    ));
+    assert!(rust.contains(
+        "/// # Errors\n///\n/// If [`device`](DriveBackupCommand::device) is not a valid block device, DeviceNotFound\n"
+    ));
+    assert!(rust.contains(
+        "/// - If [`device`](BlockPasswdCommand::device) is not a valid block device, DeviceNotFound\n\
+         /// - If [`device`](BlockPasswdCommand::device) is not encrypted, DeviceNotEncrypted\n"
+    ));
+    assert_eq!(
+        schema.doc_diagnostics(&types),
+        vec![
+            "block_passwd: @cont is not a member or a definition",
+            "eject: @optional is not a member or a definition",
+        ]
+    );
+    assert!(!rust.contains("RustcDecodable"));
+    assert!(rust.contains("\n    pub addr: serde_json::Value,\n"));
+    assert!(rust.contains("\n    #[serde(flatten)]\n    pub driver: BlockdevOptionsBranch,\n"));
+    assert!(rust.contains("\n#[serde(tag = \"driver\", rename_all = \"kebab-case\")]\n"));
+    assert!(rust.contains("    #[serde(rename = \"BLOCK_JOB_COMPLETED\")]\n    BlockJobCompleted {\n"));
+    assert!(rust.contains(
+        "#[serde(untagged)]\npub enum BlockdevRef {\n    /// references the ID of an existing block device. An empty string means that no block device should be referenced.\n    Reference(String),\n    /// defines a new block device inline\n    Definition(Box<BlockdevOptions>),\n}\n"
+    ));
+}
+
+#[test]
+fn test_generated_block_core() {
+    let schema = parse_qapi::Schema::load("tests/block-core.json").unwrap();
+    let mut types = parse_qapi::TypeMap::default();
+    for (_, dependency) in parse_qapi::DependencyGraph::new(&schema).unresolved() {
+        types.insert(&dependency.name, "serde_json::Value").unwrap();
+    }
+    // tests/roundtrip.rs compiles this file and feeds it QMP replies
+    let mut generated = String::new();
+    File::open("tests/generated/block_core.rs")
+        .unwrap()
+        .read_to_string(&mut generated)
+        .unwrap();
+    assert_eq!(schema.to_rust_with(&types).unwrap(), generated);
+}